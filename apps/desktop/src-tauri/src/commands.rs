@@ -1,4 +1,5 @@
 use crate::git::{self, Commit, FileDiff, GitError, ProjectState, SaveResult, ShipResult, SyncResult};
+use crate::journal;
 use crate::watcher;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -179,6 +180,9 @@ pub struct SavedProject {
     pub path: String,
     pub name: String,
     pub added_at: i64,
+    /// Main repository path when this project is a linked git worktree.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree_of: Option<String>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -230,13 +234,32 @@ fn persist_saved_projects(projects: &[SavedProject]) {
 pub async fn get_saved_projects() -> Result<Vec<SavedProject>, String> {
     let projects = load_saved_projects();
     // Filter out non-existent paths
-    let valid: Vec<_> = projects
+    let mut valid: Vec<_> = projects
         .into_iter()
         .filter(|p| std::path::Path::new(&p.path).exists())
         .collect();
+    // The sidebar nests worktrees under their repository; the stored order is kept
+    let worktree_of: Vec<_> = valid.iter().map(|p| saved_worktree_of(&p.path, &valid)).collect();
+    for (project, worktree_of) in valid.iter_mut().zip(worktree_of) {
+        project.worktree_of = worktree_of;
+    }
     Ok(valid)
 }
 
+/// Main repository of a worktree project, under the path it is saved as when
+/// that repository is saved too.
+fn saved_worktree_of(path: &str, projects: &[SavedProject]) -> Option<String> {
+    let canonical = |path: &str| std::fs::canonicalize(path).unwrap_or_else(|_| path.into());
+    let main = git::main_worktree_path(path)?;
+    let main_canonical = canonical(&main);
+    Some(
+        projects
+            .iter()
+            .find(|project| canonical(&project.path) == main_canonical)
+            .map_or(main, |project| project.path.clone()),
+    )
+}
+
 #[tauri::command]
 pub async fn add_saved_project(path: String) -> Result<SavedProject, String> {
     let project_path = std::path::Path::new(&path);
@@ -253,17 +276,28 @@ pub async fn add_saved_project(path: String) -> Result<SavedProject, String> {
         .map(|s| s.to_string_lossy().to_string())
         .unwrap_or_else(|| path.clone());
 
+    // Load existing, add new, save
+    let mut projects = load_saved_projects();
+    // Remove if already exists (to update timestamp)
+    projects.retain(|p| p.path != path);
+
     let project = SavedProject {
         path: path.clone(),
         name,
         added_at: chrono::Utc::now().timestamp(),
+        worktree_of: saved_worktree_of(&path, &projects),
     };
 
-    // Load existing, add new, save
-    let mut projects = load_saved_projects();
-    // Remove if already exists (to update timestamp)
-    projects.retain(|p| p.path != path);
-    projects.push(project.clone());
+    // A new worktree joins the end of its repository's group
+    let group_end = project.worktree_of.as_ref().and_then(|main| {
+        projects
+            .iter()
+            .rposition(|p| &p.path == main || p.worktree_of.as_ref() == Some(main))
+    });
+    match group_end {
+        Some(index) => projects.insert(index + 1, project.clone()),
+        None => projects.push(project.clone()),
+    }
     persist_saved_projects(&projects);
 
     Ok(project)
@@ -366,6 +400,7 @@ pub async fn git_status(
 pub async fn git_save(
     path: String,
     message: Option<String>,
+    options: Option<git::SaveOptions>,
     state: State<'_, AppState>,
 ) -> Result<SaveResult, GitError> {
     let project_path = if path.is_empty() {
@@ -379,14 +414,107 @@ pub async fn git_save(
         path
     };
 
-    let result = git::save(&project_path, message)?;
+    let before = journal::begin(&project_path);
+    let result = git::save_with_options(&project_path, message, &options.unwrap_or_default());
+    journal::record(&project_path, "save", before, vec![]);
     invalidate_status_cache(&state, &project_path);
-    Ok(result)
+    result
+}
+
+#[tauri::command]
+pub async fn git_undo_save(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<git::UndoSaveResult, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::undo_save(&project_path);
+    journal::record(&project_path, "undo_save", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_amend_save(
+    path: String,
+    message: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<SaveResult, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::amend_save(&project_path, message);
+    journal::record(&project_path, "amend_save", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_plan_rebase(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<git::RebasePlan, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::plan_rebase(&project_path)
+}
+
+#[tauri::command]
+pub async fn git_execute_rebase(
+    path: String,
+    plan: git::RebasePlan,
+    state: State<'_, AppState>,
+) -> Result<git::RebaseResult, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::execute_rebase(&project_path, &plan);
+    journal::record(&project_path, "rebase", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
 }
 
 #[tauri::command]
 pub async fn git_ship(
     path: String,
+    options: Option<git::ShipOptions>,
     state: State<'_, AppState>,
 ) -> Result<ShipResult, GitError> {
     let project_path = if path.is_empty() {
@@ -400,14 +528,17 @@ pub async fn git_ship(
         path
     };
 
-    let result = git::ship(&project_path)?;
+    let before = journal::begin(&project_path);
+    let result = git::ship_with_options(&project_path, &options.unwrap_or_default());
+    journal::record(&project_path, "ship", before, vec![]);
     invalidate_status_cache(&state, &project_path);
-    Ok(result)
+    result
 }
 
 #[tauri::command]
 pub async fn git_sync(
     path: String,
+    options: Option<git::SyncOptions>,
     state: State<'_, AppState>,
 ) -> Result<SyncResult, GitError> {
     let project_path = if path.is_empty() {
@@ -421,14 +552,58 @@ pub async fn git_sync(
         path
     };
 
-    let result = git::sync(&project_path)?;
+    let options = options.unwrap_or_default();
+    let before = journal::begin(&project_path);
+    let result = git::sync_with_options(&project_path, &options);
+    let kind = if options.push { "sync_push" } else { "sync" };
+    journal::record(&project_path, kind, before, vec![]);
     invalidate_status_cache(&state, &project_path);
-    Ok(result)
+    result
+}
+
+#[tauri::command]
+pub async fn git_get_sync_strategy(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<git::SyncStrategy, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::get_sync_strategy(&project_path)
+}
+
+#[tauri::command]
+pub async fn git_set_sync_strategy(
+    path: String,
+    strategy: git::SyncStrategy,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::set_sync_strategy(&project_path, strategy)
 }
 
 #[tauri::command]
 pub async fn git_fetch(
     path: String,
+    remote: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<(), GitError> {
     let project_path = if path.is_empty() {
@@ -442,7 +617,7 @@ pub async fn git_fetch(
         path
     };
 
-    git::fetch(&project_path)?;
+    git::fetch(&project_path, remote.as_deref())?;
     invalidate_status_cache(&state, &project_path);
     Ok(())
 }
@@ -451,6 +626,7 @@ pub async fn git_fetch(
 pub async fn git_log(
     path: String,
     limit: Option<usize>,
+    graph: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<Vec<Commit>, GitError> {
     let project_path = if path.is_empty() {
@@ -464,7 +640,69 @@ pub async fn git_log(
         path
     };
 
-    git::get_log(&project_path, limit)
+    git::get_log(&project_path, limit, graph.unwrap_or(false))
+}
+
+#[tauri::command]
+pub async fn git_query_history(
+    path: String,
+    query: Option<git::HistoryQuery>,
+    state: State<'_, AppState>,
+) -> Result<git::HistoryPage, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::query_history(&project_path, &query.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn git_show_commit(
+    path: String,
+    sha: String,
+    state: State<'_, AppState>,
+) -> Result<git::CommitDetail, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::show_commit(&project_path, &sha)
+}
+
+#[tauri::command]
+pub async fn git_compare(
+    path: String,
+    base: String,
+    head: String,
+    include_hunks: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<git::CompareResult, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::compare(&project_path, &base, &head, include_hunks.unwrap_or(false))
 }
 
 #[tauri::command]
@@ -1248,6 +1486,7 @@ pub async fn git_clone_into_folder(
     path: String,
     clone_url: String,
     branch: Option<String>,
+    recursive: Option<bool>,
 ) -> Result<(), String> {
     if clone_url.trim().is_empty() {
         return Err("Clone URL is required".to_string());
@@ -1279,126 +1518,1236 @@ pub async fn git_clone_into_folder(
             .arg("--single-branch");
     }
 
+    if recursive.unwrap_or(false) {
+        command.arg("--recurse-submodules");
+    }
+
     command
         .arg(clone_url.trim())
         .arg(".")
         .current_dir(&destination);
 
-    let output = command
-        .output()
-        .map_err(|e| format!("Failed to launch git clone: {}", e))?;
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to launch git clone: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let detail = if !stderr.is_empty() { stderr } else { stdout };
+        if detail.is_empty() {
+            return Err("Git clone failed".to_string());
+        }
+        return Err(format!("Git clone failed: {}", detail));
+    }
+
+    Ok(())
+}
+
+// Notification Commands
+
+#[tauri::command]
+pub async fn send_notification(
+    title: String,
+    body: String,
+    app: AppHandle,
+) -> Result<(), String> {
+    use tauri_plugin_notification::NotificationExt;
+    
+    app.notification()
+        .builder()
+        .title(&title)
+        .body(&body)
+        .show()
+        .map_err(|e| e.to_string())?;
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn notify_save_success(
+    files_count: usize,
+    commit_sha: String,
+    app: AppHandle,
+) -> Result<(), String> {
+    use tauri_plugin_notification::NotificationExt;
+    
+    let title = "Changes Saved ⚡";
+    let body = format!(
+        "{} file{} committed ({})",
+        files_count,
+        if files_count == 1 { "" } else { "s" },
+        &commit_sha[..7.min(commit_sha.len())]
+    );
+    
+    app.notification()
+        .builder()
+        .title(title)
+        .body(&body)
+        .show()
+        .map_err(|e| e.to_string())?;
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn notify_ship_success(
+    commits_count: usize,
+    branch: String,
+    app: AppHandle,
+) -> Result<(), String> {
+    use tauri_plugin_notification::NotificationExt;
+    
+    let title = "Changes Shipped! 🚀";
+    let body = format!(
+        "{} commit{} pushed to {}",
+        commits_count,
+        if commits_count == 1 { "" } else { "s" },
+        branch
+    );
+    
+    app.notification()
+        .builder()
+        .title(title)
+        .body(&body)
+        .show()
+        .map_err(|e| e.to_string())?;
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn notify_error(
+    title: String,
+    message: String,
+    app: AppHandle,
+) -> Result<(), String> {
+    use tauri_plugin_notification::NotificationExt;
+    
+    app.notification()
+        .builder()
+        .title(&title)
+        .body(&message)
+        .show()
+        .map_err(|e| e.to_string())?;
+    
+    Ok(())
+}
+
+// Git Extended Commands
+
+#[tauri::command]
+pub async fn git_stage(
+    path: String,
+    files: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::stage(&project_path, &files)?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn git_unstage(
+    path: String,
+    files: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::unstage(&project_path, &files)?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn git_stage_hunks(
+    path: String,
+    diff: git::DetailedFileDiff,
+    selections: Vec<git::HunkSelection>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::stage_hunks(&project_path, &diff, &selections)?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn git_unstage_hunks(
+    path: String,
+    diff: git::DetailedFileDiff,
+    selections: Vec<git::HunkSelection>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::unstage_hunks(&project_path, &diff, &selections)?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn git_discard_files(
+    path: String,
+    files: Vec<String>,
+    target: Option<git::DiscardTarget>,
+    state: State<'_, AppState>,
+) -> Result<git::DiscardResult, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::discard_files(
+        &project_path,
+        &files,
+        target.unwrap_or(git::DiscardTarget::Index),
+    );
+    let backups = result
+        .as_ref()
+        .map(|discard| discard.backups.clone())
+        .unwrap_or_default();
+    journal::record(&project_path, "discard", before, backups);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_discard_hunks(
+    path: String,
+    diff: git::DetailedFileDiff,
+    selections: Vec<git::HunkSelection>,
+    target: Option<git::DiscardTarget>,
+    state: State<'_, AppState>,
+) -> Result<git::DiscardResult, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::discard_hunks(
+        &project_path,
+        &diff,
+        &selections,
+        target.unwrap_or(git::DiscardTarget::Index),
+    );
+    let backups = result
+        .as_ref()
+        .map(|discard| discard.backups.clone())
+        .unwrap_or_default();
+    journal::record(&project_path, "discard", before, backups);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_restore_discard_backups(
+    path: String,
+    backups: Vec<git::DiscardBackup>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::restore_discard_backups(&project_path, &backups)?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn git_checkout(
+    path: String,
+    branch: String,
+    options: Option<git::CheckoutOptions>,
+    state: State<'_, AppState>,
+) -> Result<git::CheckoutResult, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::checkout_with_options(&project_path, &branch, &options.unwrap_or_default());
+    journal::record(&project_path, "checkout", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_create_branch(
+    path: String,
+    name: String,
+    checkout: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::create_branch(&project_path, &name, checkout.unwrap_or(false));
+    journal::record(&project_path, "create_branch", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_delete_branch(
+    path: String,
+    name: String,
+    force: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::delete_branch(&project_path, &name, force.unwrap_or(false));
+    journal::record(&project_path, "delete_branch", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_rename_branch(
+    path: String,
+    old_name: String,
+    new_name: String,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::rename_branch(&project_path, &old_name, &new_name);
+    journal::record(&project_path, "rename_branch", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_set_upstream(
+    path: String,
+    branch: String,
+    upstream: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::set_upstream(&project_path, &branch, upstream.as_deref())?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn git_merge_branch(
+    path: String,
+    name: String,
+    mode: Option<git::MergeMode>,
+    state: State<'_, AppState>,
+) -> Result<git::MergeResult, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::merge_branch(&project_path, &name, mode.unwrap_or_default());
+    journal::record(&project_path, "merge", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_cherry_pick(
+    path: String,
+    shas: Vec<String>,
+    options: Option<git::PickOptions>,
+    state: State<'_, AppState>,
+) -> Result<git::PickResult, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::cherry_pick(&project_path, &shas, &options.unwrap_or_default());
+    journal::record(&project_path, "cherry_pick", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_revert(
+    path: String,
+    shas: Vec<String>,
+    options: Option<git::PickOptions>,
+    state: State<'_, AppState>,
+) -> Result<git::PickResult, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::revert(&project_path, &shas, &options.unwrap_or_default());
+    journal::record(&project_path, "revert", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_delete_merged_branches(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let deleted = git::delete_merged_branches(&project_path);
+    journal::record(&project_path, "delete_merged_branches", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    deleted
+}
+
+#[tauri::command]
+pub async fn git_worktrees(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<git::Worktree>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::list_worktrees(&project_path)
+}
+
+#[tauri::command]
+pub async fn git_add_worktree(
+    path: String,
+    worktree_path: String,
+    branch: String,
+    base: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<git::Worktree, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::add_worktree(&project_path, &worktree_path, &branch, base.as_deref())
+}
+
+#[tauri::command]
+pub async fn git_lock_worktree(
+    path: String,
+    worktree_path: String,
+    reason: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::lock_worktree(&project_path, &worktree_path, reason.as_deref())
+}
+
+#[tauri::command]
+pub async fn git_unlock_worktree(
+    path: String,
+    worktree_path: String,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::unlock_worktree(&project_path, &worktree_path)
+}
+
+#[tauri::command]
+pub async fn git_prune_worktrees(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::prune_worktrees(&project_path)
+}
+
+#[tauri::command]
+pub async fn git_remove_worktree(
+    path: String,
+    worktree_path: String,
+    force: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::remove_worktree(&project_path, &worktree_path, force.unwrap_or(false))?;
+    invalidate_status_cache(&state, &worktree_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn git_submodules(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<git::SubmoduleStatus>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::list_submodules(&project_path)
+}
+
+#[tauri::command]
+pub async fn git_init_submodules(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<git::SubmoduleStatus>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let result = git::init_submodules(&project_path)?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn git_update_submodules(
+    path: String,
+    recursive: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<git::SubmoduleStatus>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let result = git::update_submodules(&project_path, recursive.unwrap_or(true))?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn git_sync_submodules(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<git::SubmoduleStatus>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let result = git::sync_submodules(&project_path)?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn git_branches(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<git::Branch>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::get_branches(&project_path)
+}
+
+#[tauri::command]
+pub async fn git_remotes(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<git::Remote>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::get_remotes(&project_path)
+}
+
+#[tauri::command]
+pub async fn git_add_remote(
+    path: String,
+    name: String,
+    url: String,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::add_remote(&project_path, &name, &url)?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn git_remove_remote(
+    path: String,
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::remove_remote(&project_path, &name)?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn git_rename_remote(
+    path: String,
+    old_name: String,
+    new_name: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let problems = git::rename_remote(&project_path, &old_name, &new_name)?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(problems)
+}
+
+#[tauri::command]
+pub async fn git_set_remote_url(
+    path: String,
+    name: String,
+    url: String,
+    push_only: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::set_remote_url(&project_path, &name, &url, push_only.unwrap_or(false))?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn git_stash_save(
+    path: String,
+    message: Option<String>,
+    options: Option<git::StashOptions>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::stash_save_with_options(&project_path, message, &options.unwrap_or_default());
+    journal::record(&project_path, "stash_save", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_stash_pop(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::stash_pop(&project_path);
+    journal::record(&project_path, "stash_pop", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_stash_list(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<git::StashEntry>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::stash_list(&project_path)
+}
+
+#[tauri::command]
+pub async fn git_stash_diff(
+    path: String,
+    index: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<git::DetailedFileDiff>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::stash_diff(&project_path, index)
+}
+
+#[tauri::command]
+pub async fn git_stash_apply(
+    path: String,
+    index: usize,
+    pop: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<git::StashApplyResult, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let pop = pop.unwrap_or(false);
+    let before = journal::begin(&project_path);
+    let result = git::stash_apply(&project_path, index, pop);
+    journal::record(&project_path, if pop { "stash_pop" } else { "stash_apply" }, before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_stash_drop(
+    path: String,
+    index: usize,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::stash_drop(&project_path, index);
+    journal::record(&project_path, "stash_drop", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_stash_branch(
+    path: String,
+    index: usize,
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::stash_branch(&project_path, index, &name);
+    journal::record(&project_path, "stash_branch", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_tags(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<git::Tag>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::list_tags(&project_path)
+}
+
+#[tauri::command]
+pub async fn git_create_tag(
+    path: String,
+    name: String,
+    target: Option<String>,
+    message: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<git::Tag, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let tag = git::create_tag(&project_path, &name, target.as_deref(), message.as_deref());
+    journal::record(&project_path, "create_tag", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    tag
+}
+
+#[tauri::command]
+pub async fn git_delete_tag(
+    path: String,
+    name: String,
+    remote: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::delete_tag(&project_path, &name, remote.as_deref());
+    journal::record(&project_path, "delete_tag", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        let detail = if !stderr.is_empty() { stderr } else { stdout };
-        if detail.is_empty() {
-            return Err("Git clone failed".to_string());
-        }
-        return Err(format!("Git clone failed: {}", detail));
-    }
+#[tauri::command]
+pub async fn git_push_tags(
+    path: String,
+    remote: Option<String>,
+    name: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
 
+    git::push_tags(&project_path, remote.as_deref(), name.as_deref())?;
+    invalidate_status_cache(&state, &project_path);
     Ok(())
 }
 
-// Notification Commands
-
 #[tauri::command]
-pub async fn send_notification(
-    title: String,
-    body: String,
-    app: AppHandle,
-) -> Result<(), String> {
-    use tauri_plugin_notification::NotificationExt;
-    
-    app.notification()
-        .builder()
-        .title(&title)
-        .body(&body)
-        .show()
-        .map_err(|e| e.to_string())?;
-    
-    Ok(())
+pub async fn git_release(
+    path: String,
+    options: git::ReleaseOptions,
+    state: State<'_, AppState>,
+) -> Result<git::ReleaseResult, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    let before = journal::begin(&project_path);
+    let result = git::release(&project_path, &options);
+    let kind = if options.ship { "ship_release" } else { "release" };
+    journal::record(&project_path, kind, before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
 }
 
 #[tauri::command]
-pub async fn notify_save_success(
-    files_count: usize,
-    commit_sha: String,
-    app: AppHandle,
-) -> Result<(), String> {
-    use tauri_plugin_notification::NotificationExt;
-    
-    let title = "Changes Saved ⚡";
-    let body = format!(
-        "{} file{} committed ({})",
-        files_count,
-        if files_count == 1 { "" } else { "s" },
-        &commit_sha[..7.min(commit_sha.len())]
-    );
-    
-    app.notification()
-        .builder()
-        .title(title)
-        .body(&body)
-        .show()
-        .map_err(|e| e.to_string())?;
-    
-    Ok(())
+pub async fn git_file_diff(
+    path: String,
+    file: String,
+    staged: Option<bool>,
+    against_index: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<git::DetailedFileDiff, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    if against_index.unwrap_or(false) {
+        return git::get_unstaged_file_diff(&project_path, &file);
+    }
+    git::get_file_diff(&project_path, &file, staged.unwrap_or(false))
 }
 
 #[tauri::command]
-pub async fn notify_ship_success(
-    commits_count: usize,
-    branch: String,
-    app: AppHandle,
-) -> Result<(), String> {
-    use tauri_plugin_notification::NotificationExt;
-    
-    let title = "Changes Shipped! 🚀";
-    let body = format!(
-        "{} commit{} pushed to {}",
-        commits_count,
-        if commits_count == 1 { "" } else { "s" },
-        branch
-    );
-    
-    app.notification()
-        .builder()
-        .title(title)
-        .body(&body)
-        .show()
-        .map_err(|e| e.to_string())?;
-    
-    Ok(())
+pub async fn git_blame(
+    path: String,
+    file: String,
+    revision: Option<String>,
+    contents: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<git::BlameLine>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::blame_file(&project_path, &file, revision.as_deref(), contents.as_deref())
 }
 
 #[tauri::command]
-pub async fn notify_error(
-    title: String,
-    message: String,
-    app: AppHandle,
-) -> Result<(), String> {
-    use tauri_plugin_notification::NotificationExt;
-    
-    app.notification()
-        .builder()
-        .title(&title)
-        .body(&message)
-        .show()
-        .map_err(|e| e.to_string())?;
-    
-    Ok(())
+pub async fn git_file_history(
+    path: String,
+    file: String,
+    limit: Option<usize>,
+    skip: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<git::FileHistoryEntry>, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::get_file_history(&project_path, &file, limit, skip)
 }
 
-// Git Extended Commands
+#[tauri::command]
+pub async fn git_conflicts(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<git::ConflictState, GitError> {
+    let project_path = if path.is_empty() {
+        state
+            .current_project
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| GitError::NotARepository("No project selected".to_string()))?
+    } else {
+        path
+    };
+
+    git::list_conflicts(&project_path)
+}
 
 #[tauri::command]
-pub async fn git_stage(
+pub async fn git_conflict_detail(
     path: String,
-    files: Vec<String>,
+    file: String,
     state: State<'_, AppState>,
-) -> Result<(), GitError> {
+) -> Result<git::ConflictDetail, GitError> {
     let project_path = if path.is_empty() {
         state
             .current_project
@@ -1410,15 +2759,14 @@ pub async fn git_stage(
         path
     };
 
-    git::stage(&project_path, &files)?;
-    invalidate_status_cache(&state, &project_path);
-    Ok(())
+    git::get_conflict_detail(&project_path, &file)
 }
 
 #[tauri::command]
-pub async fn git_unstage(
+pub async fn git_resolve_conflict(
     path: String,
-    files: Vec<String>,
+    file: String,
+    content: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<(), GitError> {
     let project_path = if path.is_empty() {
@@ -1432,17 +2780,16 @@ pub async fn git_unstage(
         path
     };
 
-    git::unstage(&project_path, &files)?;
+    git::resolve_conflict(&project_path, &file, content)?;
     invalidate_status_cache(&state, &project_path);
     Ok(())
 }
 
 #[tauri::command]
-pub async fn git_checkout(
+pub async fn git_continue_operation(
     path: String,
-    branch: String,
     state: State<'_, AppState>,
-) -> Result<(), GitError> {
+) -> Result<git::ConflictState, GitError> {
     let project_path = if path.is_empty() {
         state
             .current_project
@@ -1454,18 +2801,18 @@ pub async fn git_checkout(
         path
     };
 
-    git::checkout(&project_path, &branch)?;
+    let before = journal::begin(&project_path);
+    let result = git::continue_operation(&project_path);
+    journal::record(&project_path, "continue_operation", before, vec![]);
     invalidate_status_cache(&state, &project_path);
-    Ok(())
+    result
 }
 
 #[tauri::command]
-pub async fn git_create_branch(
+pub async fn git_skip_operation(
     path: String,
-    name: String,
-    checkout: Option<bool>,
     state: State<'_, AppState>,
-) -> Result<(), GitError> {
+) -> Result<git::ConflictState, GitError> {
     let project_path = if path.is_empty() {
         state
             .current_project
@@ -1477,16 +2824,18 @@ pub async fn git_create_branch(
         path
     };
 
-    git::create_branch(&project_path, &name, checkout.unwrap_or(false))?;
+    let before = journal::begin(&project_path);
+    let result = git::skip_operation(&project_path);
+    journal::record(&project_path, "skip_operation", before, vec![]);
     invalidate_status_cache(&state, &project_path);
-    Ok(())
+    result
 }
 
 #[tauri::command]
-pub async fn git_branches(
+pub async fn git_abort_operation(
     path: String,
     state: State<'_, AppState>,
-) -> Result<Vec<git::Branch>, GitError> {
+) -> Result<git::ConflictState, GitError> {
     let project_path = if path.is_empty() {
         state
             .current_project
@@ -1498,14 +2847,18 @@ pub async fn git_branches(
         path
     };
 
-    git::get_branches(&project_path)
+    let before = journal::begin(&project_path);
+    let result = git::abort_operation(&project_path);
+    journal::record(&project_path, "abort_operation", before, vec![]);
+    invalidate_status_cache(&state, &project_path);
+    result
 }
 
 #[tauri::command]
-pub async fn git_remotes(
+pub async fn git_health_check(
     path: String,
     state: State<'_, AppState>,
-) -> Result<Vec<git::Remote>, GitError> {
+) -> Result<git::RepoHealth, GitError> {
     let project_path = if path.is_empty() {
         state
             .current_project
@@ -1517,15 +2870,15 @@ pub async fn git_remotes(
         path
     };
 
-    git::get_remotes(&project_path)
+    git::check_repo_health(&project_path)
 }
 
 #[tauri::command]
-pub async fn git_stash_save(
+pub async fn git_recover(
     path: String,
-    message: Option<String>,
+    action: git::RecoveryAction,
     state: State<'_, AppState>,
-) -> Result<(), GitError> {
+) -> Result<git::RepoHealth, GitError> {
     let project_path = if path.is_empty() {
         state
             .current_project
@@ -1537,16 +2890,16 @@ pub async fn git_stash_save(
         path
     };
 
-    git::stash_save(&project_path, message)?;
+    let result = git::recover_repo(&project_path, &action);
     invalidate_status_cache(&state, &project_path);
-    Ok(())
+    result
 }
 
 #[tauri::command]
-pub async fn git_stash_pop(
+pub async fn git_list_operations(
     path: String,
     state: State<'_, AppState>,
-) -> Result<(), GitError> {
+) -> Result<Vec<journal::OperationRecord>, GitError> {
     let project_path = if path.is_empty() {
         state
             .current_project
@@ -1558,18 +2911,14 @@ pub async fn git_stash_pop(
         path
     };
 
-    git::stash_pop(&project_path)?;
-    invalidate_status_cache(&state, &project_path);
-    Ok(())
+    journal::list_operations(&project_path)
 }
 
 #[tauri::command]
-pub async fn git_file_diff(
+pub async fn git_undo_last_operation(
     path: String,
-    file: String,
-    staged: Option<bool>,
     state: State<'_, AppState>,
-) -> Result<git::DetailedFileDiff, GitError> {
+) -> Result<journal::OperationRecord, GitError> {
     let project_path = if path.is_empty() {
         state
             .current_project
@@ -1581,7 +2930,9 @@ pub async fn git_file_diff(
         path
     };
 
-    git::get_file_diff(&project_path, &file, staged.unwrap_or(false))
+    let result = journal::undo_last_operation(&project_path)?;
+    invalidate_status_cache(&state, &project_path);
+    Ok(result)
 }
 
 #[tauri::command]
@@ -4109,7 +5460,7 @@ pub async fn ai_generate_commit(
         First line should be under 72 characters.\n\
         If the diff is summarized, rely on the stats and file list.\n\n\
         {}",
-        if diff.len() > 10000 { &diff[..10000] } else { &diff }
+        truncate_on_char_boundary(&diff, 10000)
     );
 
     let client = reqwest::Client::new();
@@ -4195,6 +5546,54 @@ pub async fn ai_generate_commit(
     Ok(AiCommitResponse { message })
 }
 
+/// Longest prefix of `text` within `max_bytes` that does not split a character.
+fn truncate_on_char_boundary(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Per-file hunk budget in the PR prompt, so one large file cannot crowd out the rest.
+const PR_DIFF_FILE_LIMIT: usize = 1500;
+
+fn pr_diff_text(comparison: &git::CompareResult) -> String {
+    let diffs = comparison.diffs.as_deref().unwrap_or_default();
+    comparison
+        .files
+        .iter()
+        .map(|file| {
+            let mut text = format!("{} {} (+{} -{})\n", file.status, file.path, file.additions, file.deletions);
+            let hunks: String = diffs
+                .iter()
+                .find(|diff| diff.path == file.path)
+                .into_iter()
+                .flat_map(|diff| &diff.hunks)
+                .flat_map(|hunk| &hunk.lines)
+                .map(|line| {
+                    let prefix = match line.line_type.as_str() {
+                        "add" => '+',
+                        "delete" => '-',
+                        _ => ' ',
+                    };
+                    format!("{}{}", prefix, line.content)
+                })
+                .collect();
+            let truncated = truncate_on_char_boundary(&hunks, PR_DIFF_FILE_LIMIT);
+            text.push_str(truncated);
+            if truncated.len() < hunks.len() {
+                text.push_str("\n[...]\n");
+            }
+            text
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[tauri::command]
 pub async fn ai_generate_pr(
     commits: Vec<String>,
@@ -4204,7 +5603,27 @@ pub async fn ai_generate_pr(
     provider: String,
     model: String,
     api_key: String,
+    path: Option<String>,
 ) -> Result<AiPrResponse, String> {
+    // With a repository path, derive commits and the diff from the branches themselves,
+    // keeping the caller's values when the branches cannot be compared
+    let comparison = path.as_deref().and_then(|path| {
+        git::compare(path, &base_branch, &head_branch, true)
+            .or_else(|_| git::compare(path, &format!("origin/{}", base_branch), &head_branch, true))
+            .ok()
+    });
+    let (commits, diff) = match comparison {
+        Some(comparison) => {
+            let commits = comparison
+                .head_commits
+                .iter()
+                .map(|c| format!("- {}: {}", c.short_sha, c.message.lines().next().unwrap_or("")))
+                .collect();
+            (commits, pr_diff_text(&comparison))
+        }
+        None => (commits, diff),
+    };
+
     let commits_text = if commits.is_empty() {
         String::from("- No recent commits available")
     } else {
@@ -4215,13 +5634,13 @@ pub async fn ai_generate_pr(
         "Generate a pull request title and description for the following changes.\n\n\
         Branch: {} → {}\n\n\
         Commits:\n{}\n\n\
-        Diff summary (truncated):\n{}\n\n\
+        Diff (truncated):\n{}\n\n\
         Output format (JSON):\n{{\n  \"title\": \"Short descriptive title (max 72 chars)\",\n  \"description\": \"Markdown description with ## Summary and ## Changes sections\"\n}}\n\n\
         Output ONLY valid JSON, no markdown code blocks.",
         head_branch,
         base_branch,
         commits_text,
-        if diff.len() > 5000 { &diff[..5000] } else { &diff }
+        truncate_on_char_boundary(&diff, 12000)
     );
 
     let client = reqwest::Client::new();
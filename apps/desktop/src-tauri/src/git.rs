@@ -23,6 +23,24 @@ pub enum GitError {
     RebaseConflict(String),
     #[error("Push rejected: {0}")]
     PushRejected(String),
+    #[error("Already pushed: {0}")]
+    AlreadyPushed(String),
+    #[error("Branch '{0}' has commits that are not merged")]
+    BranchNotMerged(String),
+    #[error("Checkout would overwrite local changes: {}", .0.join(", "))]
+    CheckoutConflict(Vec<String>),
+    #[error("Branches have diverged: {0}")]
+    Diverged(String),
+    #[error("Network error: {0}")]
+    Network(String),
+    #[error("Commit signing failed: {0}")]
+    SigningFailed(String),
+    #[error("The {hook} hook failed: {output}")]
+    HookFailed { hook: String, output: String },
+    #[error("Cherry-pick of {sha} stopped on conflicts: {}", .conflicts.join(", "))]
+    CherryPickConflict { sha: String, conflicts: Vec<String> },
+    #[error("Revert of {sha} stopped on conflicts: {}", .conflicts.join(", "))]
+    RevertConflict { sha: String, conflicts: Vec<String> },
     #[error("IO error: {0}")]
     Io(String),
 }
@@ -71,6 +89,19 @@ pub struct ProjectState {
     pub last_commit_hash: String,
     pub last_commit_message: String,
     pub last_commit_timestamp: i64,
+    pub operation: RepoOperation,
+    pub rebase_progress: Option<RebaseProgress>,
+    pub index_locked: bool,
+    pub submodules: Vec<SubmoduleStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RebaseProgress {
+    pub current: usize,
+    pub total: usize,
+    /// Branch being rebased, since HEAD is detached while the rebase runs.
+    pub head_name: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -88,6 +119,29 @@ pub struct SaveResult {
     pub files_committed: usize,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveOptions {
+    #[serde(default)]
+    pub mode: SaveMode,
+    /// Commit without running pre-commit, commit-msg and the other hooks.
+    #[serde(default)]
+    pub skip_hooks: bool,
+}
+
+/// Which changes a save commits.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SaveMode {
+    /// Stage and commit every change.
+    #[default]
+    All,
+    /// Commit only what is already staged.
+    Staged,
+    /// Commit only these files; other staged changes stay staged.
+    Paths { paths: Vec<String> },
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ShipResult {
@@ -98,12 +152,56 @@ pub struct ShipResult {
     pub rebased: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoSaveResult {
+    pub undone_sha: String,
+    pub message: String,
+    pub head_sha: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipOptions {
+    /// Leave a conflicted pull --rebase in progress for the conflict
+    /// resolution flow instead of aborting it.
+    #[serde(default)]
+    pub pause_on_conflict: bool,
+    /// Remote to ship to; defaults to the branch's upstream remote.
+    #[serde(default)]
+    pub remote: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncOptions {
+    /// Remote to pull from; defaults to the branch's upstream remote.
+    #[serde(default)]
+    pub remote: Option<String>,
+    /// Overrides the project's configured strategy for this sync.
+    #[serde(default)]
+    pub strategy: Option<SyncStrategy>,
+    /// Push local commits once the pull succeeds.
+    #[serde(default)]
+    pub push: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncStrategy {
+    #[default]
+    FfOnly,
+    Rebase,
+    Merge,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SyncResult {
     pub pulled: usize,
     pub pushed: usize,
     pub conflicts: bool,
+    pub changed_files: Vec<FileStatus>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -118,6 +216,8 @@ pub struct Commit {
     pub parent_shas: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub refs: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graph: Option<GraphRow>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -203,6 +303,7 @@ pub fn get_status(repo_path: &str) -> Result<ProjectState, GitError> {
         .include_ignored(false);
 
     let statuses = repo.statuses(Some(&mut opts))?;
+    let submodules = submodule_statuses(&repo)?;
 
     let mut changed_files = Vec::new();
     let mut staged_files = Vec::new();
@@ -226,7 +327,9 @@ pub fn get_status(repo_path: &str) -> Result<ProjectState, GitError> {
             });
         }
 
-        if status.is_wt_modified() || status.is_wt_deleted() {
+        // Submodule checkouts are reported in `submodules` instead
+        let is_submodule = submodules.iter().any(|submodule| submodule.path == path);
+        if (status.is_wt_modified() || status.is_wt_deleted()) && !is_submodule {
             let status_str = if status.is_wt_deleted() {
                 "deleted"
             } else {
@@ -276,6 +379,36 @@ pub fn get_status(repo_path: &str) -> Result<ProjectState, GitError> {
         last_commit_hash,
         last_commit_message,
         last_commit_timestamp,
+        operation: repo.state().into(),
+        rebase_progress: read_rebase_progress(repo.path()),
+        index_locked: repo.path().join("index.lock").exists(),
+        submodules,
+    })
+}
+
+fn read_rebase_progress(git_dir: &Path) -> Option<RebaseProgress> {
+    let read_trimmed = |path: std::path::PathBuf| {
+        std::fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+    };
+
+    // Interactive/merge rebases use rebase-merge, the apply backend uses rebase-apply
+    let (dir, current_file, total_file) = if git_dir.join("rebase-merge").is_dir() {
+        (git_dir.join("rebase-merge"), "msgnum", "end")
+    } else if git_dir.join("rebase-apply").is_dir() {
+        (git_dir.join("rebase-apply"), "next", "last")
+    } else {
+        return None;
+    };
+
+    let current = read_trimmed(dir.join(current_file))?.parse().ok()?;
+    let total = read_trimmed(dir.join(total_file))?.parse().ok()?;
+    let head_name = read_trimmed(dir.join("head-name"))
+        .map(|name| name.strip_prefix("refs/heads/").unwrap_or(&name).to_string());
+
+    Some(RebaseProgress {
+        current,
+        total,
+        head_name,
     })
 }
 
@@ -298,87 +431,548 @@ fn get_ahead_behind(repo: &Repository, branch: &str) -> Result<(usize, usize, bo
     Ok((ahead, behind, true))
 }
 
-pub fn save(repo_path: &str, message: Option<String>) -> Result<SaveResult, GitError> {
-    let repo = open_repo(repo_path)?;
+/// Whether `oid` is already contained in the upstream of a local branch.
+fn is_on_upstream(repo: &Repository, branch: &str, oid: git2::Oid) -> Result<bool, GitError> {
+    let local = match repo.find_branch(branch, git2::BranchType::Local) {
+        Ok(b) => b,
+        Err(_) => return Ok(false),
+    };
 
-    // Stage all changes
-    let mut index = repo.index()?;
-    index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
-    index.write()?;
+    let upstream = match local.upstream() {
+        Ok(u) => u,
+        Err(_) => return Ok(false),
+    };
+
+    let upstream_oid = upstream.get().peel_to_commit()?.id();
+    Ok(upstream_oid == oid || repo.graph_descendant_of(upstream_oid, oid)?)
+}
+
+/// Write a commit object without moving any ref, signing it when
+/// `commit.gpgsign` is enabled.
+fn write_commit(
+    repo: &Repository,
+    author: &Signature,
+    committer: &Signature,
+    message: &str,
+    tree: &git2::Tree,
+    parents: &[&git2::Commit],
+) -> Result<git2::Oid, GitError> {
+    let config = repo.config()?;
+    if !config.get_bool("commit.gpgsign").unwrap_or(false) {
+        return Ok(repo.commit(None, author, committer, message, tree, parents)?);
+    }
+
+    let buffer = repo.commit_create_buffer(author, committer, message, tree, parents)?;
+    let buffer = buffer
+        .as_str()
+        .ok_or_else(|| GitError::SigningFailed("Commit is not valid UTF-8".to_string()))?;
+    let signature = sign_buffer(repo, &config, committer, buffer)?;
+    Ok(repo.commit_signed(buffer, &signature, None)?)
+}
+
+/// Point HEAD, or the branch it is on, at `oid` with a reflog entry like
+/// `commit (amend): <subject>`.
+fn advance_head(repo: &Repository, oid: git2::Oid, action: &str) -> Result<(), GitError> {
+    let commit = repo.find_commit(oid)?;
+    let log_message = format!("{}: {}", action, commit.summary().unwrap_or(""));
+    match repo.find_reference("HEAD")?.symbolic_target() {
+        Some(branch) => {
+            repo.reference(branch, oid, true, &log_message)?;
+        }
+        None => repo.set_head_detached(oid)?,
+    }
+    Ok(())
+}
+
+/// Sign `buffer` the way `git commit -S` would, honoring `gpg.format`,
+/// `user.signingkey` and the configured signing programs.
+fn sign_buffer(
+    repo: &Repository,
+    config: &git2::Config,
+    committer: &Signature,
+    buffer: &str,
+) -> Result<String, GitError> {
+    use std::io::Write;
+
+    let format = config.get_string("gpg.format").unwrap_or_else(|_| "openpgp".to_string());
+    let signing_key = config.get_string("user.signingkey").ok();
+    let mut literal_key_file = None;
+
+    let mut command = match format.as_str() {
+        "ssh" => {
+            let key = signing_key.ok_or_else(|| {
+                GitError::SigningFailed("user.signingkey must be set for SSH signing".to_string())
+            })?;
+            let program = config
+                .get_string("gpg.ssh.program")
+                .unwrap_or_else(|_| "ssh-keygen".to_string());
+            let mut command = std::process::Command::new(program);
+            command.args(["-Y", "sign", "-n", "git", "-f"]);
+
+            // A literal public key signs through ssh-agent
+            let literal = key.strip_prefix("key::").unwrap_or(&key);
+            if literal.starts_with("ssh-") || literal.starts_with("ecdsa-") || literal.starts_with("sk-") {
+                let path = repo.path().join("vibogit-signing-key.pub");
+                std::fs::write(&path, format!("{}\n", literal))?;
+                command.arg(&path).arg("-U");
+                literal_key_file = Some(path);
+            } else {
+                let expanded = match key.strip_prefix("~/") {
+                    Some(rest) => dirs::home_dir().map(|home| home.join(rest)).unwrap_or_else(|| key.clone().into()),
+                    None => key.clone().into(),
+                };
+                command.arg(expanded);
+            }
+            command
+        }
+        "openpgp" | "x509" => {
+            let (program_key, default_program) = if format == "x509" {
+                ("gpg.x509.program", "gpgsm")
+            } else {
+                ("gpg.program", "gpg")
+            };
+            let program = config
+                .get_string(program_key)
+                .or_else(|_| config.get_string("gpg.openpgp.program"))
+                .unwrap_or_else(|_| default_program.to_string());
+            // Like git, fall back to the committer identity as the key id
+            let key = signing_key.unwrap_or_else(|| {
+                format!("{} <{}>", committer.name().unwrap_or(""), committer.email().unwrap_or(""))
+            });
+            let mut command = std::process::Command::new(program);
+            command.args(["--status-fd=2", "-bsau", &key]);
+            command
+        }
+        other => {
+            return Err(GitError::SigningFailed(format!("Unsupported gpg.format: {}", other)));
+        }
+    };
+
+    let result = (|| {
+        let mut child = command
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| GitError::SigningFailed(format!("Failed to run signing program: {}", e)))?;
+        child
+            .stdin
+            .take()
+            .ok_or_else(|| GitError::SigningFailed("Failed to open signing program input".to_string()))?
+            .write_all(buffer.as_bytes())?;
+        let output = child.wait_with_output()?;
+
+        let signature = String::from_utf8_lossy(&output.stdout).to_string();
+        if !output.status.success() || signature.trim().is_empty() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            eprintln!("Commit signing failed: {}", stderr);
+            return Err(GitError::SigningFailed(stderr.trim().to_string()));
+        }
+        Ok(signature)
+    })();
+
+    if let Some(path) = literal_key_file {
+        let _ = std::fs::remove_file(path);
+    }
+    result
+}
+
+pub fn save(repo_path: &str, message: Option<String>) -> Result<SaveResult, GitError> {
+    save_with_options(repo_path, message, &SaveOptions::default())
+}
 
-    // Check if there's anything to commit
-    let tree_id = index.write_tree()?;
-    let tree = repo.find_tree(tree_id)?;
+pub fn save_with_options(
+    repo_path: &str,
+    message: Option<String>,
+    options: &SaveOptions,
+) -> Result<SaveResult, GitError> {
+    let repo = open_repo(repo_path)?;
 
     let parent_commit = match repo.head() {
         Ok(head) => Some(head.peel_to_commit()?),
         Err(_) => None,
     };
+    let parent_tree = parent_commit.as_ref().map(|commit| commit.tree()).transpose()?;
+
+    let mut index = repo.index()?;
+    let staged_tree = match &options.mode {
+        // Stage all changes
+        SaveMode::All => {
+            index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
+            None
+        }
+        SaveMode::Staged => None,
+        // Commit the listed files on top of HEAD, setting the rest of the
+        // staged changes aside like `git commit --only`
+        SaveMode::Paths { paths } => {
+            if paths.is_empty() {
+                return Err(GitError::Git2("No files selected to save".to_string()));
+            }
+            let staged_tree = index.write_tree()?;
+            match &parent_tree {
+                Some(tree) => index.read_tree(tree)?,
+                None => index.clear()?,
+            }
+            stage_paths(&mut index, paths)?;
+            Some(staged_tree)
+        }
+    };
+    index.write()?;
+
+    let result = commit_index(
+        &repo,
+        &mut index,
+        parent_commit.as_ref(),
+        parent_tree.as_ref(),
+        message,
+        options,
+    );
+
+    if let (Some(staged_tree), SaveMode::Paths { paths }) = (staged_tree, &options.mode) {
+        index.read_tree(&repo.find_tree(staged_tree)?)?;
+        if result.is_ok() {
+            stage_paths(&mut index, paths)?;
+        }
+        index.write()?;
+    }
+
+    result
+}
+
+/// Bring the index entries matching `paths` in line with the working tree,
+/// including deletions.
+fn stage_paths(index: &mut git2::Index, paths: &[String]) -> Result<(), GitError> {
+    index.add_all(paths.iter(), git2::IndexAddOption::DEFAULT, None)?;
+    index.update_all(paths.iter(), None)?;
+    Ok(())
+}
+
+fn count_tree_changes(
+    repo: &Repository,
+    parent_tree: Option<&git2::Tree>,
+    tree: &git2::Tree,
+) -> Result<usize, GitError> {
+    Ok(repo.diff_tree_to_tree(parent_tree, Some(tree), None)?.deltas().len())
+}
+
+/// Marks a conflicted squash merge that the next save finishes. Holds the
+/// HEAD it was started on, since git keeps no state for squash merges.
+const SQUASH_MARKER: &str = "vibogit-squash";
+
+/// The message git prepared for a squash merge still waiting to be saved.
+fn pending_squash_message(repo: &Repository) -> Option<String> {
+    let started_on = std::fs::read_to_string(repo.path().join(SQUASH_MARKER)).ok()?;
+    let head = repo.head().ok()?.target()?;
+    if started_on.trim() != head.to_string() {
+        return None;
+    }
+    std::fs::read_to_string(repo.path().join("SQUASH_MSG")).ok()
+}
+
+/// Commit the index on top of `parent`, running the commit hooks around it.
+fn commit_index(
+    repo: &Repository,
+    index: &mut git2::Index,
+    parent: Option<&git2::Commit>,
+    parent_tree: Option<&git2::Tree>,
+    message: Option<String>,
+    options: &SaveOptions,
+) -> Result<SaveResult, GitError> {
+    let mut tree = repo.find_tree(index.write_tree()?)?;
+    let mut files_committed = count_tree_changes(repo, parent_tree, &tree)?;
+    if files_committed == 0 {
+        return Err(GitError::NothingToCommit);
+    }
 
     // Get signature
     let sig = repo.signature().unwrap_or_else(|_| {
         Signature::now("ViboGit User", "user@vibogit.app").unwrap()
     });
 
-    // Generate message if not provided
-    let commit_message = message.unwrap_or_else(|| {
-        let status = get_status(repo_path).unwrap_or_else(|_| ProjectState {
-            branch: "unknown".to_string(),
-            is_detached: false,
-            changed_files: vec![],
-            staged_files: vec![],
-            untracked_files: vec![],
-            ahead: 0,
-            behind: 0,
-            has_remote: false,
-            is_empty_repo: false,
-            last_commit_hash: String::new(),
-            last_commit_message: String::new(),
-            last_commit_timestamp: 0,
-        });
-
-        let total_files = status.staged_files.len() + status.changed_files.len() + status.untracked_files.len();
-        format!("Update {} file{}", total_files, if total_files == 1 { "" } else { "s" })
+    // Generate message if not provided; a conflicted squash merge left its own
+    let squash_msg = pending_squash_message(repo);
+    let mut commit_message = message.unwrap_or_else(|| match &squash_msg {
+        Some(squash_msg) => squash_msg.trim_end().to_string(),
+        None => format!("Update {} file{}", files_committed, if files_committed == 1 { "" } else { "s" }),
     });
 
-    let parents: Vec<&git2::Commit> = parent_commit.iter().collect();
-    
-    if parents.is_empty() {
-        // Initial commit
-        let oid = repo.commit(Some("HEAD"), &sig, &sig, &commit_message, &tree, &[])?;
-        return Ok(SaveResult {
-            sha: oid.to_string(),
-            message: commit_message,
-            files_committed: 1,
+    if !options.skip_hooks {
+        run_hook(repo, "pre-commit", &[])?;
+
+        // Hooks like lint-staged may restage files
+        index.read(true)?;
+        tree = repo.find_tree(index.write_tree()?)?;
+        files_committed = count_tree_changes(repo, parent_tree, &tree)?;
+        if files_committed == 0 {
+            return Err(GitError::NothingToCommit);
+        }
+
+        commit_message = run_message_hooks(repo, commit_message)?;
+    }
+
+    let parents: Vec<&git2::Commit> = parent.into_iter().collect();
+    let oid = write_commit(repo, &sig, &sig, &commit_message, &tree, &parents)?;
+    let action = if parents.is_empty() { "commit (initial)" } else { "commit" };
+    advance_head(repo, oid, action)?;
+    if squash_msg.is_some() {
+        let _ = std::fs::remove_file(repo.path().join("SQUASH_MSG"));
+        let _ = std::fs::remove_file(repo.path().join(SQUASH_MARKER));
+    }
+
+    if !options.skip_hooks {
+        // post-commit cannot undo the commit, so its failure is only logged
+        if let Err(e) = run_hook(repo, "post-commit", &[]) {
+            eprintln!("{}", e);
+        }
+    }
+
+    Ok(SaveResult {
+        sha: oid.to_string(),
+        message: commit_message,
+        files_committed,
+    })
+}
+
+/// Directory git runs hooks from: `core.hooksPath`, relative to the working
+/// tree when not absolute, or the repository's shared `hooks` directory.
+fn hooks_dir(repo: &Repository) -> Result<std::path::PathBuf, GitError> {
+    if let Ok(path) = repo.config()?.get_path("core.hooksPath") {
+        if path.is_absolute() {
+            return Ok(path);
+        }
+        return Ok(repo_workdir(repo)?.join(path));
+    }
+
+    // Linked worktrees share the hooks of the main repository
+    let common_dir = match std::fs::read_to_string(repo.path().join("commondir")) {
+        Ok(dir) => repo.path().join(dir.trim()),
+        Err(_) => repo.path().to_path_buf(),
+    };
+    Ok(common_dir.join("hooks"))
+}
+
+#[cfg(unix)]
+fn hook_command(hook: &Path) -> Option<std::process::Command> {
+    use std::os::unix::fs::PermissionsExt;
+    let metadata = std::fs::metadata(hook).ok()?;
+    // Like git, skip hooks that are not executable
+    (metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .then(|| std::process::Command::new(hook))
+}
+
+#[cfg(not(unix))]
+fn hook_command(hook: &Path) -> Option<std::process::Command> {
+    hook.is_file().then(|| {
+        let mut command = std::process::Command::new("sh");
+        command.arg(hook);
+        command
+    })
+}
+
+/// Run a hook if it is installed. A non-zero exit fails with its output.
+fn run_hook(repo: &Repository, name: &str, args: &[&str]) -> Result<(), GitError> {
+    let Some(mut command) = hook_command(&hooks_dir(repo)?.join(name)) else {
+        return Ok(());
+    };
+
+    let output = command
+        .args(args)
+        .env("GIT_INDEX_FILE", repo.path().join("index"))
+        .env("GIT_EDITOR", ":")
+        .stdin(std::process::Stdio::null())
+        .current_dir(repo_workdir(repo)?)
+        .output()
+        .map_err(|e| GitError::Io(format!("Failed to run {} hook: {}", name, e)))?;
+
+    if !output.status.success() {
+        let text = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Err(GitError::HookFailed {
+            hook: name.to_string(),
+            output: text.trim().to_string(),
         });
     }
+    Ok(())
+}
+
+/// Pass the message through `prepare-commit-msg` and `commit-msg`, which may
+/// rewrite it in place.
+fn run_message_hooks(repo: &Repository, message: String) -> Result<String, GitError> {
+    let message_file = repo.path().join("COMMIT_EDITMSG");
+    std::fs::write(&message_file, &message)?;
+    let message_path = message_file.to_string_lossy().to_string();
+
+    run_hook(repo, "prepare-commit-msg", &[&message_path, "message"])?;
+    run_hook(repo, "commit-msg", &[&message_path])?;
+
+    let edited = std::fs::read_to_string(&message_file)?;
+    if edited == message {
+        return Ok(message);
+    }
+    let edited = git2::message_prettify(&edited, None)?.trim_end().to_string();
+    if edited.is_empty() {
+        return Err(GitError::Git2("Aborting commit due to empty commit message".to_string()));
+    }
+    Ok(edited)
+}
 
-    // Check if tree is same as parent (nothing to commit)
-    if tree.id() == parents[0].tree()?.id() {
-        return Err(GitError::NothingToCommit);
+/// Fail if HEAD is already on the upstream branch, so local history rewrites
+/// never diverge from what was shipped.
+fn ensure_head_not_pushed(repo: &Repository, action: &str) -> Result<(), GitError> {
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Ok(());
+    }
+
+    let branch_name = head.shorthand().unwrap_or("").to_string();
+    let head_oid = head.peel_to_commit()?.id();
+    if is_on_upstream(repo, &branch_name, head_oid)? {
+        return Err(GitError::AlreadyPushed(format!(
+            "Cannot {} because the last save has already been shipped",
+            action
+        )));
     }
 
-    let oid = repo.commit(Some("HEAD"), &sig, &sig, &commit_message, &tree, &parents)?;
+    Ok(())
+}
+
+/// Soft-reset HEAD by one commit, keeping its changes staged.
+pub fn undo_save(repo_path: &str) -> Result<UndoSaveResult, GitError> {
+    let repo = open_repo(repo_path)?;
+    ensure_head_not_pushed(&repo, "undo")?;
+
+    let head_commit = repo.head()?.peel_to_commit()?;
+    let parent = head_commit
+        .parent(0)
+        .map_err(|_| GitError::Git2("Cannot undo the first save in a repository".to_string()))?;
+
+    repo.reset(parent.as_object(), git2::ResetType::Soft, None)?;
+
+    Ok(UndoSaveResult {
+        undone_sha: head_commit.id().to_string(),
+        message: head_commit.message().unwrap_or("").to_string(),
+        head_sha: parent.id().to_string(),
+    })
+}
+
+/// Fold all working-tree changes, and optionally a new message, into HEAD.
+pub fn amend_save(repo_path: &str, message: Option<String>) -> Result<SaveResult, GitError> {
+    let repo = open_repo(repo_path)?;
+    ensure_head_not_pushed(&repo, "amend")?;
+
+    let head_commit = repo.head()?.peel_to_commit()?;
 
-    // Count committed files
-    let files_committed = index.len();
+    let mut index = repo.index()?;
+    index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+
+    let message_changed = message
+        .as_deref()
+        .is_some_and(|value| Some(value) != head_commit.message());
+    if tree.id() == head_commit.tree_id() && !message_changed {
+        return Err(GitError::NothingToCommit);
+    }
+
+    // Keep the original author but record who amended it, and when
+    let committer = repo.signature()?;
+    let parents: Vec<git2::Commit> = head_commit.parents().collect();
+    let oid = write_commit(
+        &repo,
+        &head_commit.author(),
+        &committer,
+        message.as_deref().or(head_commit.message()).unwrap_or(""),
+        &tree,
+        &parents.iter().collect::<Vec<_>>(),
+    )?;
+    advance_head(&repo, oid, "commit (amend)")?;
+    let amended = repo.find_commit(oid)?;
+
+    // Count files changed by the amended commit as a whole
+    let parent_tree = match amended.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let files_committed = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?
+        .deltas()
+        .len();
 
     Ok(SaveResult {
         sha: oid.to_string(),
-        message: commit_message,
+        message: amended.message().unwrap_or("").to_string(),
         files_committed,
     })
 }
 
 pub fn ship(repo_path: &str) -> Result<ShipResult, GitError> {
+    ship_with_options(repo_path, &ShipOptions::default())
+}
+
+/// The remote to talk to: the requested one, else the branch's upstream
+/// remote, else `origin`, else the only configured remote.
+fn resolve_remote(repo: &Repository, branch_name: &str, requested: Option<&str>) -> Result<String, GitError> {
+    if let Some(name) = requested {
+        repo.find_remote(name).map_err(|_| GitError::NoRemote)?;
+        return Ok(name.to_string());
+    }
+
+    if let Ok(name) = repo.branch_upstream_remote(&format!("refs/heads/{}", branch_name)) {
+        if let Some(name) = name.as_str() {
+            return Ok(name.to_string());
+        }
+    }
+
+    let remotes = repo.remotes()?;
+    if remotes.iter().flatten().any(|name| name == "origin") {
+        return Ok("origin".to_string());
+    }
+    match remotes.len() {
+        1 => remotes.get(0).map(|name| name.to_string()).ok_or(GitError::NoRemote),
+        _ => Err(GitError::NoRemote),
+    }
+}
+
+/// Like `get_ahead_behind`, but against `<remote>/<branch>` so shipping to a
+/// remote other than the upstream compares with what that remote has.
+fn get_remote_ahead_behind(
+    repo: &Repository,
+    branch: &str,
+    remote: &str,
+) -> Result<(usize, usize, bool), GitError> {
+    let local = match repo.find_branch(branch, git2::BranchType::Local) {
+        Ok(b) => b,
+        Err(_) => return Ok((0, 0, false)),
+    };
+    let remote_ref = match repo.find_reference(&format!("refs/remotes/{}/{}", remote, branch)) {
+        Ok(r) => r,
+        Err(_) => return Ok((0, 0, false)),
+    };
+
+    let local_oid = local.get().peel_to_commit()?.id();
+    let remote_oid = remote_ref.peel_to_commit()?.id();
+    let (ahead, behind) = repo.graph_ahead_behind(local_oid, remote_oid)?;
+
+    Ok((ahead, behind, true))
+}
+
+pub fn ship_with_options(repo_path: &str, options: &ShipOptions) -> Result<ShipResult, GitError> {
     // First scope: open repo to get branch name and check ahead/behind
-    let (branch_name, commits_pushed_before_push) = {
+    let (branch_name, remote_name, set_upstream, commits_pushed_before_push) = {
         let repo = open_repo(repo_path)?;
         let head = repo.head()?;
         let branch_name = head.shorthand().unwrap_or("main").to_string();
-        // Verify origin remote exists
-        let _remote = repo.find_remote("origin").map_err(|_| GitError::NoRemote)?;
-        let (ahead, _behind, has_remote) = get_ahead_behind(&repo, &branch_name)?;
+        let remote_name = resolve_remote(&repo, &branch_name, options.remote.as_deref())?;
+        // Only track the shipped-to remote when the branch has no upstream yet,
+        // or when it already tracks that remote
+        let set_upstream = repo
+            .branch_upstream_remote(&format!("refs/heads/{}", branch_name))
+            .map(|name| name.as_str() == Some(remote_name.as_str()))
+            .unwrap_or(true);
+        let (ahead, _behind, has_remote) = get_remote_ahead_behind(&repo, &branch_name, &remote_name)?;
 
         let commits_pushed_before_push = if has_remote {
             Some(ahead)
@@ -403,7 +997,7 @@ pub fn ship(repo_path: &str) -> Result<ShipResult, GitError> {
             })?)
         };
 
-        (branch_name, commits_pushed_before_push)
+        (branch_name, remote_name, set_upstream, commits_pushed_before_push)
     };
 
     let mut rebased = false;
@@ -411,7 +1005,7 @@ pub fn ship(repo_path: &str) -> Result<ShipResult, GitError> {
     // If behind remote, auto-pull with rebase before pushing
     let behind = if commits_pushed_before_push.is_some() {
         let repo = open_repo(repo_path)?;
-        let (_ahead, behind, _has_remote) = get_ahead_behind(&repo, &branch_name)?;
+        let (_ahead, behind, _has_remote) = get_remote_ahead_behind(&repo, &branch_name, &remote_name)?;
         behind
     } else {
         0
@@ -421,7 +1015,7 @@ pub fn ship(repo_path: &str) -> Result<ShipResult, GitError> {
         eprintln!("Local is {} behind remote, running git pull --rebase before push", behind);
 
         let rebase_output = std::process::Command::new("git")
-            .args(["pull", "--rebase", "origin", &branch_name])
+            .args(["pull", "--rebase", &remote_name, &branch_name])
             .current_dir(repo_path)
             .output()
             .map_err(|e| GitError::Io(format!("Failed to run git pull --rebase: {}", e)))?;
@@ -432,6 +1026,12 @@ pub fn ship(repo_path: &str) -> Result<ShipResult, GitError> {
 
             let err = classify_rebase_error(&stderr);
 
+            // Keep the rebase in progress so the conflicts can be resolved in-app
+            if matches!(&err, GitError::RebaseConflict(_)) && options.pause_on_conflict {
+                eprintln!("Rebase conflict detected, pausing rebase for resolution");
+                return Err(operation_conflict_error(repo_path, RepoOperation::Rebase));
+            }
+
             // If it's a rebase conflict, abort the rebase to restore clean state
             if matches!(&err, GitError::RebaseConflict(_)) {
                 eprintln!("Rebase conflict detected, aborting rebase...");
@@ -457,10 +1057,15 @@ pub fn ship(repo_path: &str) -> Result<ShipResult, GitError> {
 
     // Re-check ahead count after potential rebase to get accurate commits_pushed
     // Use git CLI for push - it has proper credential helper support
-    eprintln!("Pushing via git CLI: origin/{}", branch_name);
-    
+    eprintln!("Pushing via git CLI: {}/{}", remote_name, branch_name);
+
+    let mut push_args = vec!["push"];
+    if set_upstream {
+        push_args.push("-u");
+    }
+    push_args.extend([remote_name.as_str(), branch_name.as_str()]);
     let output = std::process::Command::new("git")
-        .args(["push", "-u", "origin", &branch_name])
+        .args(&push_args)
         .current_dir(repo_path)
         .output()
         .map_err(|e| GitError::Io(format!("Failed to run git push: {}", e)))?;
@@ -476,82 +1081,174 @@ pub fn ship(repo_path: &str) -> Result<ShipResult, GitError> {
     Ok(ShipResult {
         pushed: true,
         commits_pushed,
-        remote: "origin".to_string(),
+        remote: remote_name,
         branch: branch_name,
         rebased,
     })
 }
 
 pub fn sync(repo_path: &str) -> Result<SyncResult, GitError> {
-    let repo = open_repo(repo_path)?;
-
-    let head = repo.head()?;
-    let branch_name = head.shorthand().unwrap_or("main").to_string();
+    sync_with_options(repo_path, &SyncOptions::default())
+}
 
-    // Verify origin remote exists
-    let _remote = repo.find_remote("origin").map_err(|_| GitError::NoRemote)?;
-    
-    // Use git CLI for pull - it has proper credential helper support
-    eprintln!("Pulling via git CLI: origin/{}", branch_name);
-    
-    let output = std::process::Command::new("git")
-        .args(["pull", "--ff-only", "origin", &branch_name])
-        .current_dir(repo_path)
-        .output()
-        .map_err(|e| GitError::Io(format!("Failed to run git pull: {}", e)))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        eprintln!("Git pull failed: {}", stderr);
-        // Check for merge conflict
-        if stderr.contains("conflict") || stderr.contains("CONFLICT") {
-            return Err(GitError::MergeConflict);
-        }
-        return Err(GitError::AuthFailed(stderr.trim().to_string()));
-    }
-    
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let pulled = if stdout.contains("Already up to date") { 0 } else { 1 };
+const SYNC_STRATEGY_KEY: &str = "vibogit.syncStrategy";
 
-    Ok(SyncResult {
-        pulled,
-        pushed: 0,
-        conflicts: false,
-    })
+/// The project's sync strategy, stored in the repository's local git config.
+pub fn get_sync_strategy(repo_path: &str) -> Result<SyncStrategy, GitError> {
+    let repo = open_repo(repo_path)?;
+    let strategy = match repo.config()?.get_string(SYNC_STRATEGY_KEY).ok().as_deref() {
+        Some("rebase") => SyncStrategy::Rebase,
+        Some("merge") => SyncStrategy::Merge,
+        _ => SyncStrategy::FfOnly,
+    };
+    Ok(strategy)
 }
 
-pub fn fetch(repo_path: &str) -> Result<(), GitError> {
+pub fn set_sync_strategy(repo_path: &str, strategy: SyncStrategy) -> Result<(), GitError> {
     let repo = open_repo(repo_path)?;
+    let value = match strategy {
+        SyncStrategy::FfOnly => "ff-only",
+        SyncStrategy::Rebase => "rebase",
+        SyncStrategy::Merge => "merge",
+    };
+    repo.config()?.open_level(git2::ConfigLevel::Local)?.set_str(SYNC_STRATEGY_KEY, value)?;
+    Ok(())
+}
 
-    // Verify origin remote exists
-    let _remote = repo.find_remote("origin").map_err(|_| GitError::NoRemote)?;
+pub fn sync_with_options(repo_path: &str, options: &SyncOptions) -> Result<SyncResult, GitError> {
+    let (branch_name, remote_name, before_oid) = {
+        let repo = open_repo(repo_path)?;
+        if repo.state() != git2::RepositoryState::Clean || !list_conflicts(repo_path)?.conflicts.is_empty() {
+            return Err(GitError::Git2(
+                "Finish or abort the operation in progress first".to_string(),
+            ));
+        }
+        let head = repo.head()?;
+        let branch_name = head.shorthand().unwrap_or("main").to_string();
+        let remote_name = resolve_remote(&repo, &branch_name, options.remote.as_deref())?;
+        let before_oid = head.peel_to_commit()?.id();
+        (branch_name, remote_name, before_oid)
+    };
+    let strategy = match options.strategy {
+        Some(strategy) => strategy,
+        None => get_sync_strategy(repo_path)?,
+    };
+
+    // Use git CLI for pull - it has proper credential helper support
+    eprintln!("Pulling via git CLI ({:?}): {}/{}", strategy, remote_name, branch_name);
 
+    let strategy_flag = match strategy {
+        SyncStrategy::FfOnly => "--ff-only",
+        SyncStrategy::Rebase => "--rebase",
+        SyncStrategy::Merge => "--no-rebase",
+    };
     let output = std::process::Command::new("git")
-        .args(["fetch", "origin"])
+        .args(["pull", strategy_flag, "--no-edit", &remote_name, &branch_name])
+        .env("GIT_EDITOR", "true")
         .current_dir(repo_path)
         .output()
-        .map_err(|e| GitError::Io(format!("Failed to run git fetch: {}", e)))?;
+        .map_err(|e| GitError::Io(format!("Failed to run git pull: {}", e)))?;
 
-    if !output.status.success() {
+    // Conflicts leave the merge or rebase in progress for the resolution flow
+    let conflicts = !list_conflicts(repo_path)?.conflicts.is_empty();
+    if !output.status.success() && !conflicts {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        eprintln!("Git fetch failed: {}", stderr);
-        return Err(GitError::AuthFailed(stderr.trim().to_string()));
+        eprintln!("Git pull failed: {}", stderr);
+        return Err(classify_pull_error(&stderr));
     }
 
-    Ok(())
-}
-
-pub fn get_log(repo_path: &str, limit: Option<usize>) -> Result<Vec<Commit>, GitError> {
     let repo = open_repo(repo_path)?;
+    let after_oid = repo.head()?.peel_to_commit()?.id();
 
-    // Build a map of OID -> list of ref names for decoration
-    let mut ref_map: std::collections::HashMap<git2::Oid, Vec<String>> = std::collections::HashMap::new();
-    if let Ok(refs) = repo.references() {
-        for reference in refs.flatten() {
-            if let Some(name) = reference.shorthand() {
-                if let Ok(target) = reference.peel_to_commit() {
-                    ref_map.entry(target.id()).or_default().push(name.to_string());
-                }
+    // Count commits that came from the remote rather than comparing output text;
+    // a pull stopped on conflicts has not brought any in yet
+    let pulled = if conflicts {
+        0
+    } else {
+        match repo.find_reference(&format!("refs/remotes/{}/{}", remote_name, branch_name)) {
+            Ok(remote_ref) => repo.graph_ahead_behind(remote_ref.peel_to_commit()?.id(), before_oid)?.0,
+            Err(_) if after_oid != before_oid => repo.graph_ahead_behind(after_oid, before_oid)?.0,
+            Err(_) => 0,
+        }
+    };
+
+    let mut changed_files = Vec::new();
+    if after_oid != before_oid {
+        let old_tree = repo.find_commit(before_oid)?.tree()?;
+        let new_tree = repo.find_commit(after_oid)?.tree()?;
+        let diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)?;
+        for delta in diff.deltas() {
+            let path = delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let status = match delta.status() {
+                git2::Delta::Added => "added",
+                git2::Delta::Deleted => "deleted",
+                git2::Delta::Renamed => "renamed",
+                _ => "modified",
+            };
+            changed_files.push(FileStatus {
+                path,
+                status: status.to_string(),
+            });
+        }
+    }
+
+    let mut pushed = 0;
+    if options.push && !conflicts {
+        let ship_options = ShipOptions {
+            remote: Some(remote_name),
+            ..Default::default()
+        };
+        pushed = ship_with_options(repo_path, &ship_options)?.commits_pushed;
+    }
+
+    Ok(SyncResult {
+        pulled,
+        pushed,
+        conflicts,
+        changed_files,
+    })
+}
+
+/// Fetch from `remote`, defaulting to the current branch's upstream remote.
+pub fn fetch(repo_path: &str, remote: Option<&str>) -> Result<(), GitError> {
+    let repo = open_repo(repo_path)?;
+
+    let branch_name = repo
+        .head()
+        .ok()
+        .and_then(|head| head.shorthand().map(|name| name.to_string()))
+        .unwrap_or_default();
+    let remote_name = resolve_remote(&repo, &branch_name, remote)?;
+
+    let output = std::process::Command::new("git")
+        .args(["fetch", &remote_name])
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| GitError::Io(format!("Failed to run git fetch: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        eprintln!("Git fetch failed: {}", stderr);
+        return Err(classify_pull_error(&stderr));
+    }
+
+    Ok(())
+}
+
+/// Ref names pointing at each commit, with HEAD first, for log decorations.
+fn ref_decorations(repo: &Repository) -> std::collections::HashMap<git2::Oid, Vec<String>> {
+    let mut ref_map: std::collections::HashMap<git2::Oid, Vec<String>> = std::collections::HashMap::new();
+    if let Ok(refs) = repo.references() {
+        for reference in refs.flatten() {
+            if let Some(name) = reference.shorthand() {
+                if let Ok(target) = reference.peel_to_commit() {
+                    ref_map.entry(target.id()).or_default().push(name.to_string());
+                }
             }
         }
     }
@@ -567,48 +1264,396 @@ pub fn get_log(repo_path: &str, limit: Option<usize>) -> Result<Vec<Commit>, Git
             }
         }
     }
+    ref_map
+}
 
-    let mut revwalk = repo.revwalk()?;
-    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
-    revwalk.push_head()?;
-    for branch in repo.branches(None)? {
-        let (branch, _) = branch?;
-        if let Some(target) = branch.get().target() {
-            let _ = revwalk.push(target);
+/// Recent commits across HEAD and every local branch, optionally with graph
+/// lanes laid out.
+pub fn get_log(repo_path: &str, limit: Option<usize>, graph: bool) -> Result<Vec<Commit>, GitError> {
+    let query = HistoryQuery {
+        scope: HistoryScope::AllBranches,
+        limit,
+        graph,
+        ..Default::default()
+    };
+    Ok(query_history(repo_path, &query)?.commits)
+}
+
+/// Which refs a history query walks from.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum HistoryScope {
+    #[default]
+    Head,
+    AllBranches,
+    Ref { name: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryQuery {
+    pub scope: HistoryScope,
+    pub limit: Option<usize>,
+    /// Matching commits to skip; ignored when `cursor` is set.
+    pub skip: Option<usize>,
+    /// Continuation token from a previous page.
+    pub cursor: Option<String>,
+    /// Case-insensitive substring of the author name or email.
+    pub author: Option<String>,
+    /// Case-insensitive substring of the committer name or email.
+    pub committer: Option<String>,
+    /// Case-insensitive substring of the commit message.
+    pub message: Option<String>,
+    /// Unix seconds, inclusive.
+    pub since: Option<i64>,
+    /// Unix seconds, inclusive.
+    pub until: Option<i64>,
+    /// Only commits that change this file or directory.
+    pub path: Option<String>,
+    /// Lay out graph lanes for each returned commit. Lanes are computed over
+    /// every walked commit, so filtered pages keep the lines of hidden ones.
+    pub graph: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPage {
+    pub commits: Vec<Commit>,
+    /// Pass back as `cursor` to load the next page; `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// A lane in the commit graph, drawn in `column` with palette index `color`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphLane {
+    pub column: usize,
+    pub color: usize,
+}
+
+/// Line from a commit down to the lane its parent continues in.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphEdge {
+    pub parent_sha: String,
+    pub from_column: usize,
+    pub to_column: usize,
+    pub color: usize,
+}
+
+/// How to draw one history row. Columns stay put between rows; a lane only
+/// changes column through a merge or parent edge.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphRow {
+    pub column: usize,
+    pub color: usize,
+    /// Whether a line enters the commit from the row above in its own column.
+    pub has_incoming: bool,
+    /// Other lanes that end in this commit, e.g. branches forking here.
+    pub merging: Vec<GraphLane>,
+    /// Lanes that cross the row without touching the commit.
+    pub pass_through: Vec<GraphLane>,
+    pub parents: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct GraphLayout {
+    /// Commit each column is waiting for, with its color.
+    lanes: Vec<Option<(git2::Oid, usize)>>,
+    next_color: usize,
+}
+
+impl GraphLayout {
+    fn open_lane(&mut self, oid: git2::Oid, color: Option<usize>) -> (usize, usize) {
+        let color = color.unwrap_or_else(|| {
+            self.next_color += 1;
+            self.next_color - 1
+        });
+        let column = match self.lanes.iter().position(|lane| lane.is_none()) {
+            Some(column) => column,
+            None => {
+                self.lanes.push(None);
+                self.lanes.len() - 1
+            }
+        };
+        self.lanes[column] = Some((oid, color));
+        (column, color)
+    }
+
+    fn lane_for(&self, oid: git2::Oid) -> Option<(usize, usize)> {
+        self.lanes
+            .iter()
+            .position(|lane| lane.is_some_and(|(waiting, _)| waiting == oid))
+            .map(|column| (column, self.lanes[column].unwrap().1))
+    }
+
+    /// Place the next commit in walk order and reserve lanes for its parents.
+    fn advance(&mut self, oid: git2::Oid, parents: &[git2::Oid]) -> GraphRow {
+        let incoming = self.lane_for(oid);
+        let (column, color) = match incoming {
+            Some(lane) => lane,
+            None => self.open_lane(oid, None),
+        };
+
+        let mut merging = Vec::new();
+        let mut pass_through = Vec::new();
+        for (other, lane) in self.lanes.iter_mut().enumerate() {
+            match lane {
+                Some((waiting, lane_color)) if other != column => {
+                    let lane_info = GraphLane { column: other, color: *lane_color };
+                    if *waiting == oid {
+                        merging.push(lane_info);
+                        *lane = None;
+                    } else {
+                        pass_through.push(lane_info);
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.lanes[column] = None;
+
+        let mut edges = Vec::new();
+        for (index, parent) in parents.iter().enumerate() {
+            // The first parent always continues the commit's own lane; lanes
+            // waiting for the same commit join up when it is reached.
+            let (to_column, edge_color) = if index == 0 {
+                self.lanes[column] = Some((*parent, color));
+                (column, color)
+            } else {
+                match self.lane_for(*parent) {
+                    Some(lane) => lane,
+                    None => self.open_lane(*parent, None),
+                }
+            };
+            edges.push(GraphEdge {
+                parent_sha: parent.to_string(),
+                from_column: column,
+                to_column,
+                color: edge_color,
+            });
+        }
+
+        while matches!(self.lanes.last(), Some(None)) {
+            self.lanes.pop();
+        }
+
+        GraphRow {
+            column,
+            color,
+            has_incoming: incoming.is_some(),
+            merging,
+            pass_through,
+            parents: edges,
         }
     }
 
-    let limit = limit.unwrap_or(50);
-    let mut commits = Vec::new();
+    /// `<next color>;<sha>.<color>,-,...` with `-` for free columns.
+    fn to_token(&self) -> String {
+        let lanes: Vec<String> = self
+            .lanes
+            .iter()
+            .map(|lane| match lane {
+                Some((oid, color)) => format!("{}.{}", oid, color),
+                None => "-".to_string(),
+            })
+            .collect();
+        format!("{};{}", self.next_color, lanes.join(","))
+    }
 
-    for (i, oid) in revwalk.enumerate() {
-        if i >= limit {
-            break;
+    fn from_token(token: &str) -> Option<Self> {
+        let (next_color, lanes) = token.split_once(';')?;
+        let lanes = lanes
+            .split(',')
+            .filter(|lane| !lane.is_empty())
+            .map(|lane| match lane {
+                "-" => Some(None),
+                _ => {
+                    let (oid, color) = lane.split_once('.')?;
+                    Some(Some((git2::Oid::from_str(oid).ok()?, color.parse().ok()?)))
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            lanes,
+            next_color: next_color.parse().ok()?,
+        })
+    }
+}
+
+struct HistoryCursor {
+    walked: usize,
+    tips: Vec<git2::Oid>,
+    layout: Option<GraphLayout>,
+}
+
+/// Cursors are `<walked>:<tip>,<tip>[:<graph layout>]` so a page resumes from
+/// the same tips, and the same lanes, even if branches moved in between.
+fn parse_history_cursor(cursor: &str) -> Result<HistoryCursor, GitError> {
+    let invalid = || GitError::Git2(format!("Invalid history cursor: {}", cursor));
+    let mut parts = cursor.splitn(3, ':');
+    let walked = parts
+        .next()
+        .and_then(|walked| walked.parse::<usize>().ok())
+        .ok_or_else(invalid)?;
+    let tips = parts
+        .next()
+        .ok_or_else(invalid)?
+        .split(',')
+        .filter(|tip| !tip.is_empty())
+        .map(|tip| git2::Oid::from_str(tip).map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let layout = match parts.next() {
+        Some(token) => Some(GraphLayout::from_token(token).ok_or_else(invalid)?),
+        None => None,
+    };
+    Ok(HistoryCursor { walked, tips, layout })
+}
+
+fn history_tips(repo: &Repository, scope: &HistoryScope) -> Result<Vec<git2::Oid>, GitError> {
+    let mut tips = Vec::new();
+    match scope {
+        HistoryScope::Head => tips.push(repo.head()?.peel_to_commit()?.id()),
+        HistoryScope::AllBranches => {
+            if let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) {
+                tips.push(head.id());
+            }
+            for branch in repo.branches(None)? {
+                let (branch, _) = branch?;
+                if let Ok(target) = branch.get().peel_to_commit() {
+                    if !tips.contains(&target.id()) {
+                        tips.push(target.id());
+                    }
+                }
+            }
+        }
+        HistoryScope::Ref { name } => {
+            let commit = repo
+                .revparse_single(name)
+                .and_then(|object| object.peel_to_commit())
+                .map_err(|_| GitError::Git2(format!("Unknown ref: {}", name)))?;
+            tips.push(commit.id());
+        }
+    }
+    Ok(tips)
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+fn signature_matches(signature: &git2::Signature, needle: &str) -> bool {
+    contains_ignore_case(signature.name().unwrap_or(""), needle)
+        || contains_ignore_case(signature.email().unwrap_or(""), needle)
+}
+
+/// Whether the commit changes `path` relative to its first parent.
+fn commit_touches_path(commit: &git2::Commit, path: &Path) -> Result<bool, GitError> {
+    let entry_id = |tree: &git2::Tree| tree.get_path(path).ok().map(|entry| entry.id());
+    let current = entry_id(&commit.tree()?);
+    let previous = match commit.parent(0) {
+        Ok(parent) => entry_id(&parent.tree()?),
+        Err(_) => None,
+    };
+    Ok(current != previous)
+}
+
+fn history_matches(commit: &git2::Commit, query: &HistoryQuery) -> Result<bool, GitError> {
+    let time = commit.time().seconds();
+    if query.since.is_some_and(|since| time < since) || query.until.is_some_and(|until| time > until) {
+        return Ok(false);
+    }
+    if let Some(author) = &query.author {
+        if !signature_matches(&commit.author(), author) {
+            return Ok(false);
+        }
+    }
+    if let Some(committer) = &query.committer {
+        if !signature_matches(&commit.committer(), committer) {
+            return Ok(false);
+        }
+    }
+    if let Some(message) = &query.message {
+        if !contains_ignore_case(commit.message().unwrap_or(""), message) {
+            return Ok(false);
+        }
+    }
+    if let Some(path) = query.path.as_deref().filter(|path| !path.is_empty()) {
+        return commit_touches_path(commit, Path::new(path));
+    }
+    Ok(true)
+}
+
+/// One page of history for the given scope and filters, newest first.
+pub fn query_history(repo_path: &str, query: &HistoryQuery) -> Result<HistoryPage, GitError> {
+    let repo = open_repo(repo_path)?;
+    let ref_map = ref_decorations(&repo);
+
+    let (mut walked, tips, mut skip, layout) = match &query.cursor {
+        Some(cursor) => {
+            let cursor = parse_history_cursor(cursor)?;
+            (cursor.walked, cursor.tips, 0, cursor.layout)
         }
+        None => (0, history_tips(&repo, &query.scope)?, query.skip.unwrap_or(0), None),
+    };
+    let mut layout = match (query.graph, layout) {
+        (false, _) => None,
+        (true, Some(layout)) => Some(layout),
+        (true, None) if walked == 0 => Some(GraphLayout::default()),
+        (true, None) => {
+            return Err(GitError::Git2(
+                "History cursor was created without graph layout".to_string(),
+            ))
+        }
+    };
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    for tip in &tips {
+        revwalk.push(*tip)?;
+    }
+
+    let limit = query.limit.unwrap_or(50);
+    let mut commits = Vec::new();
+    let mut has_more = false;
 
+    for oid in revwalk.skip(walked) {
         let oid = oid?;
         let commit = repo.find_commit(oid)?;
+        let matches = history_matches(&commit, query)?;
+        if matches && skip == 0 && commits.len() >= limit {
+            has_more = true;
+            break;
+        }
+        walked += 1;
 
-        let parent_shas: Vec<String> = commit
-            .parents()
-            .map(|p| p.id().to_string())
-            .collect();
+        let row = layout
+            .as_mut()
+            .map(|layout| layout.advance(oid, &commit.parent_ids().collect::<Vec<_>>()));
+        if !matches {
+            continue;
+        }
+        if skip > 0 {
+            skip -= 1;
+            continue;
+        }
 
-        let refs = ref_map.get(&oid).cloned();
-
-        commits.push(Commit {
-            sha: commit.id().to_string(),
-            short_sha: commit.id().to_string()[..7].to_string(),
-            message: commit.message().unwrap_or("").to_string(),
-            author: commit.author().name().unwrap_or("Unknown").to_string(),
-            email: commit.author().email().unwrap_or("").to_string(),
-            timestamp: commit.time().seconds(),
-            parent_shas,
-            refs,
-        });
+        let mut entry = commit_info(&commit);
+        entry.refs = ref_map.get(&oid).cloned();
+        entry.graph = row;
+        commits.push(entry);
     }
 
-    Ok(commits)
+    let next_cursor = has_more.then(|| {
+        let tips: Vec<String> = tips.iter().map(|tip| tip.to_string()).collect();
+        match &layout {
+            Some(layout) => format!("{}:{}:{}", walked, tips.join(","), layout.to_token()),
+            None => format!("{}:{}", walked, tips.join(",")),
+        }
+    });
+
+    Ok(HistoryPage { commits, next_cursor })
 }
 
 pub fn get_diff(repo_path: &str) -> Result<Vec<FileDiff>, GitError> {
@@ -704,6 +1749,12 @@ pub struct DetailedFileDiff {
     pub hunks: Vec<DetailedDiffHunk>,
     pub old_path: Option<String>,
     pub is_binary: bool,
+    /// The file does not exist on the old side, e.g. untracked or intent-to-add.
+    #[serde(default)]
+    pub is_new: bool,
+    /// The file does not exist on the new side.
+    #[serde(default)]
+    pub is_deleted: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -757,26 +1808,179 @@ pub fn unstage(repo_path: &str, files: &[String]) -> Result<(), GitError> {
     Ok(())
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckoutMode {
+    /// Refuse when local changes would be overwritten.
+    #[default]
+    Safe,
+    /// Stash local changes, switch, then re-apply them.
+    Autostash,
+    /// Merge local changes into the target like `git checkout --merge`.
+    Carry,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckoutOptions {
+    #[serde(default)]
+    pub mode: CheckoutMode,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckoutResult {
+    /// Branch now checked out, or the sha when HEAD is detached.
+    pub head: String,
+    /// Local tracking branch created for a remote branch.
+    pub created_branch: Option<String>,
+    /// Files left conflicted by carried or re-applied changes.
+    pub conflicts: Vec<ConflictEntry>,
+    /// The autostash could not be re-applied cleanly and is still on the stash list.
+    pub stash_kept: bool,
+}
+
+enum CheckoutTarget {
+    Local(String),
+    Remote { local_name: String, upstream: String },
+    Detached,
+}
+
 pub fn checkout(repo_path: &str, branch_or_ref: &str) -> Result<(), GitError> {
+    checkout_with_options(repo_path, branch_or_ref, &CheckoutOptions::default()).map(|_| ())
+}
+
+pub fn checkout_with_options(
+    repo_path: &str,
+    branch_or_ref: &str,
+    options: &CheckoutOptions,
+) -> Result<CheckoutResult, GitError> {
     let repo = open_repo(repo_path)?;
 
-    // Try as branch first
-    if let Ok(branch) = repo.find_branch(branch_or_ref, git2::BranchType::Local) {
+    // Local branch, then remote branch (checked out as a new tracking branch), then commit SHA
+    let (target, commit) = if let Ok(branch) = repo.find_branch(branch_or_ref, git2::BranchType::Local) {
         let refname = branch.get().name().ok_or_else(|| GitError::Git2("Invalid branch name".to_string()))?;
-        repo.set_head(refname)?;
-        repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))?;
-        return Ok(());
+        (CheckoutTarget::Local(refname.to_string()), branch.get().peel_to_commit()?)
+    } else if let Ok(remote_branch) = repo.find_branch(branch_or_ref, git2::BranchType::Remote) {
+        let refname = remote_branch.get().name().unwrap_or("").to_string();
+        let remote_name = repo.branch_remote_name(&refname)?;
+        let remote_name = remote_name.as_str().unwrap_or("origin");
+        let local_name = branch_or_ref
+            .strip_prefix(&format!("{}/", remote_name))
+            .unwrap_or(branch_or_ref)
+            .to_string();
+
+        match repo.find_branch(&local_name, git2::BranchType::Local) {
+            Ok(existing) => {
+                let tracks_remote = existing
+                    .upstream()
+                    .ok()
+                    .and_then(|u| u.get().name().map(|n| n == refname))
+                    .unwrap_or(false);
+                if !tracks_remote {
+                    return Err(GitError::Git2(format!(
+                        "A local branch named '{}' already exists and does not track {}",
+                        local_name, branch_or_ref
+                    )));
+                }
+                let local_ref = existing.get().name().unwrap_or("").to_string();
+                (CheckoutTarget::Local(local_ref), existing.get().peel_to_commit()?)
+            }
+            Err(_) => (
+                CheckoutTarget::Remote {
+                    local_name,
+                    upstream: branch_or_ref.to_string(),
+                },
+                remote_branch.get().peel_to_commit()?,
+            ),
+        }
+    } else if let Ok(oid) = git2::Oid::from_str(branch_or_ref) {
+        (CheckoutTarget::Detached, repo.find_commit(oid)?)
+    } else {
+        return Err(GitError::Git2(format!("Could not find branch or commit: {}", branch_or_ref)));
+    };
+
+    let mut stashed = false;
+    if options.mode == CheckoutMode::Autostash {
+        let mut status_opts = StatusOptions::new();
+        status_opts.include_untracked(false);
+        if !repo.statuses(Some(&mut status_opts))?.is_empty() {
+            stash_save(repo_path, Some("vibogit autostash".to_string()))?;
+            stashed = true;
+        }
     }
 
-    // Try as commit SHA
-    if let Ok(oid) = git2::Oid::from_str(branch_or_ref) {
-        repo.find_commit(oid)?;
-        repo.set_head_detached(oid)?;
-        repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))?;
-        return Ok(());
+    let mut conflicts = vec![];
+    if options.mode == CheckoutMode::Carry {
+        let output = std::process::Command::new("git")
+            .args(["checkout", "--merge", "--detach", &commit.id().to_string()])
+            .current_dir(repo_path)
+            .output()
+            .map_err(|e| GitError::Io(format!("Failed to run git checkout: {}", e)))?;
+
+        conflicts = list_conflicts(repo_path)?.conflicts;
+        if !output.status.success() && conflicts.is_empty() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(GitError::Git2(stderr.trim().to_string()));
+        }
+    } else {
+        let mut overwritten = vec![];
+        let checkout_result = {
+            let mut builder = git2::build::CheckoutBuilder::new();
+            builder
+                .safe()
+                .notify_on(git2::CheckoutNotificationType::CONFLICT)
+                .notify(|_, path, _, _, _| {
+                    if let Some(path) = path {
+                        overwritten.push(path.to_string_lossy().to_string());
+                    }
+                    true
+                });
+            repo.checkout_tree(commit.as_object(), Some(&mut builder))
+        };
+
+        if let Err(err) = checkout_result {
+            if stashed {
+                pop_autostash(repo_path)?;
+            }
+            if overwritten.is_empty() {
+                return Err(err.into());
+            }
+            return Err(GitError::CheckoutConflict(overwritten));
+        }
+    }
+
+    let (head, created_branch) = match target {
+        CheckoutTarget::Local(refname) => {
+            repo.set_head(&refname)?;
+            (refname.trim_start_matches("refs/heads/").to_string(), None)
+        }
+        CheckoutTarget::Remote { local_name, upstream } => {
+            let mut branch = repo.branch(&local_name, &commit, false)?;
+            branch.set_upstream(Some(&upstream))?;
+            let refname = branch.get().name().unwrap_or("").to_string();
+            repo.set_head(&refname)?;
+            (local_name.clone(), Some(local_name))
+        }
+        CheckoutTarget::Detached => {
+            repo.set_head_detached(commit.id())?;
+            (commit.id().to_string(), None)
+        }
+    };
+
+    let mut stash_kept = false;
+    if stashed {
+        let applied = pop_autostash(repo_path)?;
+        stash_kept = !applied.dropped;
+        conflicts = applied.conflicts;
     }
 
-    Err(GitError::Git2(format!("Could not find branch or commit: {}", branch_or_ref)))
+    Ok(CheckoutResult {
+        head,
+        created_branch,
+        conflicts,
+        stash_kept,
+    })
 }
 
 pub fn create_branch(repo_path: &str, name: &str, checkout_after: bool) -> Result<(), GitError> {
@@ -843,72 +2047,510 @@ pub fn get_branches(repo_path: &str) -> Result<Vec<Branch>, GitError> {
     Ok(branches)
 }
 
-pub fn get_remotes(repo_path: &str) -> Result<Vec<Remote>, GitError> {
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeMode {
+    /// Fast-forward when possible, otherwise create a merge commit.
+    #[default]
+    Ff,
+    FfOnly,
+    NoFf,
+    /// Combine the branch's changes into a single new commit.
+    Squash,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeResult {
+    pub head_sha: String,
+    pub up_to_date: bool,
+    pub fast_forward: bool,
+    /// Files left conflicted; the merge stays in progress until they are resolved.
+    pub conflicts: Vec<ConflictEntry>,
+}
+
+/// Delete a local branch. Without `force` the branch must be merged into its
+/// upstream, or into HEAD when it has none.
+pub fn delete_branch(repo_path: &str, name: &str, force: bool) -> Result<(), GitError> {
     let repo = open_repo(repo_path)?;
+    let mut branch = repo.find_branch(name, git2::BranchType::Local)?;
+    if branch.is_head() {
+        return Err(GitError::Git2(format!("Cannot delete the checked out branch '{}'", name)));
+    }
 
-    let mut remotes = Vec::new();
-    
-    for remote_name in repo.remotes()?.iter().flatten() {
-        if let Ok(remote) = repo.find_remote(remote_name) {
-            let fetch_url = remote.url().unwrap_or("").to_string();
-            let push_url = remote.pushurl().unwrap_or(remote.url().unwrap_or("")).to_string();
-            
-            remotes.push(Remote {
-                name: remote_name.to_string(),
-                refs: RemoteRefs {
-                    fetch: fetch_url,
-                    push: push_url,
-                },
-            });
+    if !force {
+        let branch_oid = branch.get().peel_to_commit()?.id();
+        let target_oid = match branch.upstream() {
+            Ok(upstream) => upstream.get().peel_to_commit()?.id(),
+            Err(_) => repo.head()?.peel_to_commit()?.id(),
+        };
+        if branch_oid != target_oid && !repo.graph_descendant_of(target_oid, branch_oid)? {
+            return Err(GitError::BranchNotMerged(name.to_string()));
         }
     }
 
-    Ok(remotes)
+    branch.delete()?;
+    Ok(())
 }
 
-pub fn stash_save(repo_path: &str, message: Option<String>) -> Result<(), GitError> {
-    let mut repo = open_repo(repo_path)?;
-
-    let sig = repo.signature().unwrap_or_else(|_| {
-        Signature::now("ViboGit User", "user@vibogit.app").unwrap()
-    });
-
-    let msg = message.as_deref();
-    repo.stash_save(&sig, msg.unwrap_or("WIP"), None)?;
-
+/// Rename a local branch; its tracking config moves with it.
+pub fn rename_branch(repo_path: &str, old_name: &str, new_name: &str) -> Result<(), GitError> {
+    let repo = open_repo(repo_path)?;
+    let mut branch = repo.find_branch(old_name, git2::BranchType::Local)?;
+    branch.rename(new_name, false)?;
     Ok(())
 }
 
-pub fn stash_pop(repo_path: &str) -> Result<(), GitError> {
-    let mut repo = open_repo(repo_path)?;
+/// Track `upstream` (e.g. `origin/main`) from `branch`, or stop tracking with `None`.
+pub fn set_upstream(repo_path: &str, branch: &str, upstream: Option<&str>) -> Result<(), GitError> {
+    let repo = open_repo(repo_path)?;
+    let mut local = repo.find_branch(branch, git2::BranchType::Local)?;
+    local.set_upstream(upstream)?;
+    Ok(())
+}
+
+/// Merge `name` into the current branch.
+pub fn merge_branch(repo_path: &str, name: &str, mode: MergeMode) -> Result<MergeResult, GitError> {
+    let fast_forward = {
+        let repo = open_repo(repo_path)?;
+        let head_oid = repo.head()?.peel_to_commit()?.id();
+        let their_oid = repo.revparse_single(name)?.peel_to_commit()?.id();
+
+        let annotated = repo.find_annotated_commit(their_oid)?;
+        let (analysis, _) = repo.merge_analysis(&[&annotated])?;
+        if analysis.is_up_to_date() {
+            return Ok(MergeResult {
+                head_sha: head_oid.to_string(),
+                up_to_date: true,
+                fast_forward: false,
+                conflicts: vec![],
+            });
+        }
+        if mode == MergeMode::FfOnly && !analysis.is_fast_forward() {
+            return Err(GitError::Git2(format!(
+                "Cannot fast-forward to '{}' because the branches have diverged",
+                name
+            )));
+        }
+
+        analysis.is_fast_forward() && matches!(mode, MergeMode::Ff | MergeMode::FfOnly)
+    };
+
+    let flag = match mode {
+        MergeMode::Ff => "--ff",
+        MergeMode::FfOnly => "--ff-only",
+        MergeMode::NoFf => "--no-ff",
+        MergeMode::Squash => "--squash",
+    };
+
+    let output = std::process::Command::new("git")
+        .args(["merge", flag, "--no-edit", name])
+        .env("GIT_EDITOR", "true")
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| GitError::Io(format!("Failed to run git merge: {}", e)))?;
+
+    let conflicts = list_conflicts(repo_path)?.conflicts;
+    if !output.status.success() && conflicts.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        eprintln!("git merge failed: {}", stderr);
+        return Err(GitError::Git2(stderr.trim().to_string()));
+    }
+
+    // A squash merge leaves no MERGE_HEAD to continue from, so saving finishes it
+    if mode == MergeMode::Squash && !conflicts.is_empty() {
+        let repo = open_repo(repo_path)?;
+        let head_oid = repo.head()?.peel_to_commit()?.id();
+        std::fs::write(repo.path().join(SQUASH_MARKER), head_oid.to_string())?;
+    } else if mode == MergeMode::Squash {
+        // A squash merge only stages the combined changes
+        let output = std::process::Command::new("git")
+            .args(["commit", "--no-edit"])
+            .env("GIT_EDITOR", "true")
+            .current_dir(repo_path)
+            .output()
+            .map_err(|e| GitError::Io(format!("Failed to commit squash merge: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(GitError::Git2(stderr.trim().to_string()));
+        }
+    }
+
+    let repo = open_repo(repo_path)?;
+    let new_head = repo.head()?.peel_to_commit()?.id();
+
+    Ok(MergeResult {
+        head_sha: new_head.to_string(),
+        up_to_date: false,
+        fast_forward,
+        conflicts,
+    })
+}
+
+/// The branch `origin/HEAD` points at, falling back to `main` or `master`.
+fn default_branch_name(repo: &Repository) -> Option<String> {
+    if let Ok(reference) = repo.find_reference("refs/remotes/origin/HEAD") {
+        if let Some(target) = reference.symbolic_target() {
+            if let Some(name) = target.strip_prefix("refs/remotes/origin/") {
+                return Some(name.to_string());
+            }
+        }
+    }
+
+    ["main", "master"]
+        .into_iter()
+        .find(|name| repo.find_branch(name, git2::BranchType::Local).is_ok())
+        .map(|name| name.to_string())
+}
+
+/// Delete every local branch already merged into the default branch. Returns
+/// the names of the deleted branches.
+pub fn delete_merged_branches(repo_path: &str) -> Result<Vec<String>, GitError> {
+    let repo = open_repo(repo_path)?;
+    let default_name = default_branch_name(&repo)
+        .ok_or_else(|| GitError::Git2("Could not determine the default branch".to_string()))?;
+    let default_oid = repo
+        .find_branch(&default_name, git2::BranchType::Local)?
+        .get()
+        .peel_to_commit()?
+        .id();
+
+    let mut deleted = Vec::new();
+    for branch_result in repo.branches(Some(git2::BranchType::Local))? {
+        let (mut branch, _) = branch_result?;
+        let name = branch.name()?.unwrap_or("").to_string();
+        if name == default_name || branch.is_head() {
+            continue;
+        }
+
+        let oid = branch.get().peel_to_commit()?.id();
+        if oid != default_oid && !repo.graph_descendant_of(default_oid, oid)? {
+            continue;
+        }
+
+        // Branches checked out in other worktrees cannot be deleted
+        match branch.delete() {
+            Ok(()) => deleted.push(name),
+            Err(e) => eprintln!("Skipping merged branch {}: {}", name, e.message()),
+        }
+    }
+
+    Ok(deleted)
+}
+
+pub fn get_remotes(repo_path: &str) -> Result<Vec<Remote>, GitError> {
+    let repo = open_repo(repo_path)?;
+
+    let mut remotes = Vec::new();
+    
+    for remote_name in repo.remotes()?.iter().flatten() {
+        if let Ok(remote) = repo.find_remote(remote_name) {
+            let fetch_url = remote.url().unwrap_or("").to_string();
+            let push_url = remote.pushurl().unwrap_or(remote.url().unwrap_or("")).to_string();
+            
+            remotes.push(Remote {
+                name: remote_name.to_string(),
+                refs: RemoteRefs {
+                    fetch: fetch_url,
+                    push: push_url,
+                },
+            });
+        }
+    }
+
+    Ok(remotes)
+}
+
+pub fn add_remote(repo_path: &str, name: &str, url: &str) -> Result<(), GitError> {
+    let repo = open_repo(repo_path)?;
+    repo.remote(name, url)?;
+    Ok(())
+}
+
+/// Remove a remote along with its remote-tracking branches.
+pub fn remove_remote(repo_path: &str, name: &str) -> Result<(), GitError> {
+    let repo = open_repo(repo_path)?;
+    repo.find_remote(name).map_err(|_| GitError::NoRemote)?;
+    repo.remote_delete(name)?;
+    Ok(())
+}
+
+/// Rename a remote, moving its tracking branches and branch upstream config.
+/// Returns refspecs git could not rename automatically.
+pub fn rename_remote(repo_path: &str, old_name: &str, new_name: &str) -> Result<Vec<String>, GitError> {
+    let repo = open_repo(repo_path)?;
+    repo.find_remote(old_name).map_err(|_| GitError::NoRemote)?;
+    let problems = repo.remote_rename(old_name, new_name)?;
+    Ok(problems.iter().flatten().map(|refspec| refspec.to_string()).collect())
+}
+
+/// Change a remote's fetch URL, or only its push URL when `push_only` is set.
+pub fn set_remote_url(repo_path: &str, name: &str, url: &str, push_only: bool) -> Result<(), GitError> {
+    let repo = open_repo(repo_path)?;
+    repo.find_remote(name).map_err(|_| GitError::NoRemote)?;
+    if push_only {
+        repo.remote_set_pushurl(name, Some(url))?;
+    } else {
+        repo.remote_set_url(name, url)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StashOptions {
+    #[serde(default)]
+    pub include_untracked: bool,
+    /// Leave staged changes in place after stashing them.
+    #[serde(default)]
+    pub keep_index: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StashEntry {
+    pub index: usize,
+    pub sha: String,
+    pub message: String,
+    pub branch: Option<String>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StashApplyResult {
+    /// Files left conflicted in the working tree; a conflicted pop keeps the stash.
+    pub conflicts: Vec<ConflictEntry>,
+    pub dropped: bool,
+}
+
+pub fn stash_save(repo_path: &str, message: Option<String>) -> Result<(), GitError> {
+    stash_save_with_options(repo_path, message, &StashOptions::default())
+}
+
+pub fn stash_save_with_options(
+    repo_path: &str,
+    message: Option<String>,
+    options: &StashOptions,
+) -> Result<(), GitError> {
+    let mut repo = open_repo(repo_path)?;
+
+    let sig = repo.signature().unwrap_or_else(|_| {
+        Signature::now("ViboGit User", "user@vibogit.app").unwrap()
+    });
+
+    let mut flags = git2::StashFlags::DEFAULT;
+    if options.include_untracked {
+        flags |= git2::StashFlags::INCLUDE_UNTRACKED;
+    }
+    if options.keep_index {
+        flags |= git2::StashFlags::KEEP_INDEX;
+    }
+
+    let msg = message.as_deref();
+    repo.stash_save(&sig, msg.unwrap_or("WIP"), Some(flags))?;
+
+    Ok(())
+}
+
+pub fn stash_pop(repo_path: &str) -> Result<(), GitError> {
+    let mut repo = open_repo(repo_path)?;
 
     repo.stash_pop(0, None)?;
 
     Ok(())
 }
 
+/// Branch name from a stash message such as "WIP on main: abc1234 msg" or "On main: msg".
+fn stash_branch_name(message: &str) -> Option<String> {
+    let rest = message
+        .strip_prefix("WIP on ")
+        .or_else(|| message.strip_prefix("On "))?;
+    let branch = rest.split(':').next()?.trim();
+    if branch.is_empty() || branch == "(no branch)" {
+        return None;
+    }
+    Some(branch.to_string())
+}
+
+pub fn stash_list(repo_path: &str) -> Result<Vec<StashEntry>, GitError> {
+    let mut repo = open_repo(repo_path)?;
+
+    let mut stashes = Vec::new();
+    repo.stash_foreach(|index, message, oid| {
+        stashes.push((index, message.to_string(), *oid));
+        true
+    })?;
+
+    stashes
+        .into_iter()
+        .map(|(index, message, oid)| {
+            let commit = repo.find_commit(oid)?;
+            Ok(StashEntry {
+                index,
+                sha: oid.to_string(),
+                branch: stash_branch_name(&message),
+                message,
+                timestamp: commit.time().seconds(),
+            })
+        })
+        .collect()
+}
+
+fn find_stash(repo: &mut Repository, index: usize) -> Result<git2::Oid, GitError> {
+    let mut found = None;
+    repo.stash_foreach(|i, _, oid| {
+        if i == index {
+            found = Some(*oid);
+            return false;
+        }
+        true
+    })?;
+    found.ok_or_else(|| GitError::Git2(format!("No stash at index {}", index)))
+}
+
+/// Changes recorded in a stash, including untracked files it captured.
+pub fn stash_diff(repo_path: &str, index: usize) -> Result<Vec<DetailedFileDiff>, GitError> {
+    let mut repo = open_repo(repo_path)?;
+    let stash_oid = find_stash(&mut repo, index)?;
+    let stash = repo.find_commit(stash_oid)?;
+
+    let base_tree = stash.parent(0)?.tree()?;
+    let diff = repo.diff_tree_to_tree(Some(&base_tree), Some(&stash.tree()?), None)?;
+    let mut files = detailed_file_diffs(&diff)?;
+
+    // The third parent holds untracked files when the stash included them
+    if let Ok(untracked) = stash.parent(2) {
+        let diff = repo.diff_tree_to_tree(None, Some(&untracked.tree()?), None)?;
+        files.extend(detailed_file_diffs(&diff)?);
+    }
+
+    Ok(files)
+}
+
+/// Apply the stash at `index`, dropping it afterwards when `pop` is set and
+/// it applied cleanly.
+pub fn stash_apply(repo_path: &str, index: usize, pop: bool) -> Result<StashApplyResult, GitError> {
+    run_stash_apply(repo_path, index, pop, false)
+}
+
+/// Pop the stash made by an autostash checkout. Staged changes come back
+/// staged unless git cannot rebuild the index, then they return unstaged.
+fn pop_autostash(repo_path: &str) -> Result<StashApplyResult, GitError> {
+    match run_stash_apply(repo_path, 0, true, true) {
+        Ok(applied) => Ok(applied),
+        Err(_) => stash_apply(repo_path, 0, true),
+    }
+}
+
+fn run_stash_apply(
+    repo_path: &str,
+    index: usize,
+    pop: bool,
+    restore_index: bool,
+) -> Result<StashApplyResult, GitError> {
+    let stash_ref = format!("stash@{{{}}}", index);
+    find_stash(&mut open_repo(repo_path)?, index)?;
+
+    let command = if pop { "pop" } else { "apply" };
+    let mut args = vec!["stash", command];
+    if restore_index {
+        args.push("--index");
+    }
+    args.push(&stash_ref);
+    let output = std::process::Command::new("git")
+        .args(&args)
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| GitError::Io(format!("Failed to run git stash {}: {}", command, e)))?;
+
+    let conflicts = list_conflicts(repo_path)?.conflicts;
+    if !output.status.success() && conflicts.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Git2(stderr.trim().to_string()));
+    }
+
+    Ok(StashApplyResult {
+        dropped: pop && conflicts.is_empty(),
+        conflicts,
+    })
+}
+
+pub fn stash_drop(repo_path: &str, index: usize) -> Result<(), GitError> {
+    let mut repo = open_repo(repo_path)?;
+    find_stash(&mut repo, index)?;
+    repo.stash_drop(index)?;
+    Ok(())
+}
+
+/// Check out a new branch at the commit the stash was made from, apply the
+/// stash there and drop it.
+pub fn stash_branch(repo_path: &str, index: usize, name: &str) -> Result<(), GitError> {
+    let stash_ref = format!("stash@{{{}}}", index);
+    find_stash(&mut open_repo(repo_path)?, index)?;
+
+    let output = std::process::Command::new("git")
+        .args(["stash", "branch", name, &stash_ref])
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| GitError::Io(format!("Failed to run git stash branch: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Git2(stderr.trim().to_string()));
+    }
+
+    Ok(())
+}
+
+/// Diff of a file against HEAD: the staged changes when `staged`, otherwise
+/// staged and unstaged changes together. Selections for
+/// `DiscardTarget::Head` refer to the latter.
 pub fn get_file_diff(repo_path: &str, file_path: &str, staged: bool) -> Result<DetailedFileDiff, GitError> {
     let repo = open_repo(repo_path)?;
 
     let mut opts = DiffOptions::new();
     opts.pathspec(file_path);
 
+    let head = repo.head()?.peel_to_tree()?;
     let diff = if staged {
-        let head = repo.head()?.peel_to_tree()?;
         repo.diff_tree_to_index(Some(&head), None, Some(&mut opts))?
     } else {
-        let head = repo.head()?.peel_to_tree()?;
+        opts.include_untracked(true).show_untracked_content(true);
         repo.diff_tree_to_workdir_with_index(Some(&head), Some(&mut opts))?
     };
 
+    single_file_diff(&diff, file_path)
+}
+
+/// Diff of a file from the index to the working tree, so already staged
+/// changes are left out. Selections for `stage_hunks` and
+/// `DiscardTarget::Index` refer to this diff.
+pub fn get_unstaged_file_diff(repo_path: &str, file_path: &str) -> Result<DetailedFileDiff, GitError> {
+    let repo = open_repo(repo_path)?;
+
+    let mut opts = DiffOptions::new();
+    opts.pathspec(file_path).include_untracked(true).show_untracked_content(true);
+    let diff = repo.diff_index_to_workdir(None, Some(&mut opts))?;
+
+    single_file_diff(&diff, file_path)
+}
+
+fn single_file_diff(diff: &git2::Diff, file_path: &str) -> Result<DetailedFileDiff, GitError> {
     let mut result = DetailedFileDiff {
         path: file_path.to_string(),
         hunks: vec![],
         old_path: None,
         is_binary: false,
+        is_new: false,
+        is_deleted: false,
     };
 
     diff.print(git2::DiffFormat::Patch, |delta, hunk, line| {
+        result.is_new = matches!(delta.status(), git2::Delta::Added | git2::Delta::Untracked);
+        result.is_deleted = delta.status() == git2::Delta::Deleted;
+
         // Check binary
         if delta.new_file().is_binary() || delta.old_file().is_binary() {
             result.is_binary = true;
@@ -963,456 +2605,5571 @@ pub fn get_file_diff(repo_path: &str, file_path: &str, staged: bool) -> Result<D
     Ok(result)
 }
 
-pub fn init_repo(path: &str) -> Result<(), GitError> {
-    Repository::init(path)?;
-    Ok(())
+fn commit_info(commit: &git2::Commit) -> Commit {
+    Commit {
+        sha: commit.id().to_string(),
+        short_sha: commit.id().to_string()[..7].to_string(),
+        message: commit.message().unwrap_or("").to_string(),
+        author: commit.author().name().unwrap_or("Unknown").to_string(),
+        email: commit.author().email().unwrap_or("").to_string(),
+        timestamp: commit.time().seconds(),
+        parent_shas: commit.parents().map(|p| p.id().to_string()).collect(),
+        refs: None,
+        graph: None,
+    }
 }
 
-/// Classify a git push stderr message into the appropriate GitError variant.
-/// This is extracted for testability.
-pub fn classify_push_error(stderr: &str, rebased: bool) -> GitError {
-    let stderr_lower = stderr.to_lowercase();
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameLine {
+    pub line_number: usize,
+    /// Line number in the commit that introduced the line.
+    pub original_line_number: usize,
+    /// Path in that commit, which differs from the current path after a rename.
+    pub original_path: Option<String>,
+    pub content: String,
+    pub summary: String,
+    /// `None` for lines that are not yet saved.
+    pub commit: Option<Commit>,
+}
 
-    // Auth failures
-    if stderr_lower.contains("authentication")
-        || stderr_lower.contains("could not read username")
-        || stderr_lower.contains("permission denied")
-    {
-        return GitError::AuthFailed(stderr.trim().to_string());
-    }
+/// Blame every line of `file_path` at `revision`. Without a revision the
+/// unsaved `contents` (or the working-tree file) are blamed on top of HEAD,
+/// so uncommitted lines come back without a commit.
+pub fn blame_file(
+    repo_path: &str,
+    file_path: &str,
+    revision: Option<&str>,
+    contents: Option<&str>,
+) -> Result<Vec<BlameLine>, GitError> {
+    let repo = open_repo(repo_path)?;
 
-    // Non-fast-forward or rejected
-    if stderr_lower.contains("rejected")
-        || stderr_lower.contains("non-fast-forward")
-        || stderr_lower.contains("failed to push")
-    {
-        return GitError::PushRejected(stderr.trim().to_string());
-    }
+    let mut opts = git2::BlameOptions::new();
+    let text = match revision {
+        Some(revision) => {
+            let commit = repo.revparse_single(revision)?.peel_to_commit()?;
+            opts.newest_commit(commit.id());
+            let blob = commit.tree()?.get_path(Path::new(file_path))?.to_object(&repo)?.peel_to_blob()?;
+            String::from_utf8_lossy(blob.content()).to_string()
+        }
+        None => match contents {
+            Some(contents) => contents.to_string(),
+            None => std::fs::read_to_string(repo_workdir(&repo)?.join(file_path))?,
+        },
+    };
 
-    // If rebase succeeded but push failed, give a descriptive error
-    if rebased {
-        return GitError::PushRejected(format!(
-            "Rebase succeeded but push failed: {}",
-            stderr.trim()
-        ));
+    let committed = match repo.blame_file(Path::new(file_path), Some(&mut opts)) {
+        Ok(blame) => Some(blame),
+        // A file that was never saved has no history yet
+        Err(e) if e.code() == ErrorCode::NotFound && revision.is_none() => None,
+        Err(e) => return Err(e.into()),
+    };
+    let buffered = match (&committed, revision) {
+        (Some(blame), None) => Some(blame.blame_buffer(text.as_bytes())?),
+        _ => None,
+    };
+    let blame = buffered.as_ref().or(committed.as_ref());
+
+    let mut commits: std::collections::HashMap<git2::Oid, Commit> = std::collections::HashMap::new();
+    let mut lines = Vec::new();
+    for (idx, content) in text.lines().enumerate() {
+        let line_number = idx + 1;
+        let hunk = blame.and_then(|b| b.get_line(line_number));
+
+        let (commit, original_line_number, original_path) = match hunk {
+            Some(hunk) if !hunk.final_commit_id().is_zero() => {
+                let oid = hunk.final_commit_id();
+                let commit = match commits.entry(oid) {
+                    std::collections::hash_map::Entry::Occupied(entry) => entry.get().clone(),
+                    std::collections::hash_map::Entry::Vacant(entry) => {
+                        entry.insert(commit_info(&repo.find_commit(oid)?)).clone()
+                    }
+                };
+                let offset = line_number - hunk.final_start_line();
+                (
+                    Some(commit),
+                    hunk.orig_start_line() + offset,
+                    hunk.path().map(|p| p.to_string_lossy().to_string()),
+                )
+            }
+            _ => (None, line_number, None),
+        };
+
+        lines.push(BlameLine {
+            line_number,
+            original_line_number,
+            original_path,
+            content: content.to_string(),
+            summary: commit
+                .as_ref()
+                .map(|c| c.message.lines().next().unwrap_or("").to_string())
+                .unwrap_or_else(|| "Not yet saved".to_string()),
+            commit,
+        });
     }
 
-    GitError::PushRejected(stderr.trim().to_string())
+    Ok(lines)
 }
 
-/// Classify a git pull --rebase stderr message into the appropriate GitError variant.
-/// Returns None if it looks like a conflict (caller should abort rebase first).
-pub fn classify_rebase_error(stderr: &str) -> GitError {
-    let stderr_lower = stderr.to_lowercase();
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryEntry {
+    pub commit: Commit,
+    /// Path of the file in this commit.
+    pub path: String,
+    /// Previous path when this commit renamed the file.
+    pub old_path: Option<String>,
+    pub status: String,
+    pub additions: usize,
+    pub deletions: usize,
+}
 
-    if stderr_lower.contains("conflict")
-        || stderr_lower.contains("could not apply")
-        || stderr_lower.contains("merge conflict")
-    {
-        return GitError::RebaseConflict(
-            "Rebase conflict detected. Your local changes conflict with remote changes. Please resolve conflicts manually.".to_string()
-        );
-    }
+struct FileChange {
+    status: String,
+    old_path: Option<String>,
+    additions: usize,
+    deletions: usize,
+}
 
-    if stderr_lower.contains("authentication")
-        || stderr_lower.contains("could not read username")
-        || stderr_lower.contains("permission denied")
-    {
-        return GitError::AuthFailed(stderr.trim().to_string());
-    }
+/// The change a commit made to `path` relative to `parent_tree`, with rename
+/// detection when the file did not exist in the parent.
+fn file_change_in_commit(
+    repo: &Repository,
+    parent_tree: Option<&git2::Tree>,
+    tree: &git2::Tree,
+    path: &str,
+) -> Result<Option<FileChange>, GitError> {
+    let in_parent = parent_tree.is_some_and(|t| t.get_path(Path::new(path)).is_ok());
 
-    GitError::PushRejected(format!("Pull --rebase failed: {}", stderr.trim()))
+    let mut opts = DiffOptions::new();
+    if in_parent || parent_tree.is_none() {
+        opts.pathspec(path);
+    }
+    let mut diff = repo.diff_tree_to_tree(parent_tree, Some(tree), Some(&mut opts))?;
+    if !in_parent && parent_tree.is_some() {
+        let mut find = git2::DiffFindOptions::new();
+        find.renames(true);
+        diff.find_similar(Some(&mut find))?;
+    }
+
+    for (idx, delta) in diff.deltas().enumerate() {
+        let new_path = delta.new_file().path().map(|p| p.to_string_lossy().to_string());
+        let old_path = delta.old_file().path().map(|p| p.to_string_lossy().to_string());
+        let touches_path = new_path.as_deref() == Some(path)
+            || (delta.status() == git2::Delta::Deleted && old_path.as_deref() == Some(path));
+        if !touches_path {
+            continue;
+        }
+
+        let (additions, deletions) = match git2::Patch::from_diff(&diff, idx)? {
+            Some(patch) => {
+                let (_, additions, deletions) = patch.line_stats()?;
+                (additions, deletions)
+            }
+            None => (0, 0),
+        };
+        let status = match delta.status() {
+            git2::Delta::Added => "added",
+            git2::Delta::Deleted => "deleted",
+            git2::Delta::Renamed => "renamed",
+            _ => "modified",
+        };
+        return Ok(Some(FileChange {
+            status: status.to_string(),
+            old_path: if delta.status() == git2::Delta::Renamed { old_path } else { None },
+            additions,
+            deletions,
+        }));
+    }
+
+    Ok(None)
+}
+
+/// Commits on HEAD that touched `file_path`, newest first, following the file
+/// back through renames. Paginated like `get_log`, with an optional offset.
+pub fn get_file_history(
+    repo_path: &str,
+    file_path: &str,
+    limit: Option<usize>,
+    skip: Option<usize>,
+) -> Result<Vec<FileHistoryEntry>, GitError> {
+    let repo = open_repo(repo_path)?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    revwalk.push_head()?;
+
+    let limit = limit.unwrap_or(50);
+    let skip = skip.unwrap_or(0);
+    let mut path = file_path.to_string();
+    let mut matched = 0;
+    let mut entries = Vec::new();
+
+    for oid in revwalk {
+        if entries.len() >= limit {
+            break;
+        }
+
+        let commit = repo.find_commit(oid?)?;
+        let tree = commit.tree()?;
+        let entry_id = tree.get_path(Path::new(&path)).ok().map(|e| e.id());
+
+        // Merges only count when they changed the file relative to every parent
+        let parents: Vec<git2::Commit> = commit.parents().collect();
+        let parent_trees = parents.iter().map(|p| p.tree()).collect::<Result<Vec<_>, _>>()?;
+        let unchanged = parent_trees
+            .iter()
+            .any(|t| t.get_path(Path::new(&path)).ok().map(|e| e.id()) == entry_id);
+        if unchanged {
+            continue;
+        }
+
+        let Some(change) = file_change_in_commit(&repo, parent_trees.first(), &tree, &path)? else {
+            continue;
+        };
+
+        if matched >= skip {
+            entries.push(FileHistoryEntry {
+                commit: commit_info(&commit),
+                path: path.clone(),
+                old_path: change.old_path.clone(),
+                status: change.status.clone(),
+                additions: change.additions,
+                deletions: change.deletions,
+            });
+        }
+        matched += 1;
+
+        match change.old_path {
+            Some(old_path) => path = old_path,
+            // The file was created here; nothing older belongs to it
+            None if change.status == "added" => break,
+            None => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Convert every file in a diff to the `DetailedFileDiff` format.
+fn detailed_file_diffs(diff: &git2::Diff) -> Result<Vec<DetailedFileDiff>, GitError> {
+    let mut files = Vec::new();
+
+    for (idx, delta) in diff.deltas().enumerate() {
+        let new_path = delta.new_file().path().map(|p| p.to_string_lossy().to_string());
+        let old_path = delta.old_file().path().map(|p| p.to_string_lossy().to_string());
+        let path = new_path.clone().or_else(|| old_path.clone()).unwrap_or_default();
+
+        let mut result = DetailedFileDiff {
+            old_path: old_path.filter(|old| Some(old) != new_path.as_ref()),
+            path,
+            hunks: vec![],
+            is_binary: false,
+            is_new: matches!(delta.status(), git2::Delta::Added | git2::Delta::Untracked),
+            is_deleted: delta.status() == git2::Delta::Deleted,
+        };
+
+        let patch = git2::Patch::from_diff(diff, idx)?;
+        let is_binary = delta.new_file().is_binary()
+            || delta.old_file().is_binary()
+            || patch.as_ref().is_some_and(|p| p.delta().flags().is_binary());
+
+        if is_binary {
+            result.is_binary = true;
+        } else if let Some(patch) = patch {
+            for hunk_idx in 0..patch.num_hunks() {
+                let (hunk, line_count) = patch.hunk(hunk_idx)?;
+                let mut lines = Vec::with_capacity(line_count);
+                for line_idx in 0..line_count {
+                    let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                    let line_type = match line.origin() {
+                        '+' => "add",
+                        '-' => "delete",
+                        _ => "context",
+                    };
+                    lines.push(DetailedDiffLine {
+                        line_type: line_type.to_string(),
+                        content: String::from_utf8_lossy(line.content()).to_string(),
+                        old_line_number: line.old_lineno(),
+                        new_line_number: line.new_lineno(),
+                    });
+                }
+                result.hunks.push(DetailedDiffHunk {
+                    old_start: hunk.old_start(),
+                    old_lines: hunk.old_lines(),
+                    new_start: hunk.new_start(),
+                    new_lines: hunk.new_lines(),
+                    lines,
+                });
+            }
+        }
+
+        files.push(result);
+    }
+
+    Ok(files)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedFile {
+    pub path: String,
+    pub old_path: Option<String>,
+    pub status: String,
+    pub additions: usize,
+    pub deletions: usize,
+    pub is_binary: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitDetail {
+    pub commit: Commit,
+    pub committer: String,
+    pub committer_email: String,
+    pub committer_timestamp: i64,
+    pub files: Vec<ChangedFile>,
+    /// Hunks for each entry in `files`, in the same order.
+    pub diffs: Vec<DetailedFileDiff>,
+}
+
+/// Per-file status and line counts for a diff.
+fn changed_files(diff: &git2::Diff) -> Result<Vec<ChangedFile>, GitError> {
+    let mut files = Vec::new();
+
+    for (idx, delta) in diff.deltas().enumerate() {
+        let new_path = delta.new_file().path().map(|p| p.to_string_lossy().to_string());
+        let old_path = delta.old_file().path().map(|p| p.to_string_lossy().to_string());
+
+        let patch = git2::Patch::from_diff(diff, idx)?;
+        let is_binary = delta.new_file().is_binary()
+            || delta.old_file().is_binary()
+            || patch.as_ref().is_some_and(|p| p.delta().flags().is_binary());
+        let (additions, deletions) = match patch {
+            Some(patch) if !is_binary => {
+                let (_, additions, deletions) = patch.line_stats()?;
+                (additions, deletions)
+            }
+            _ => (0, 0),
+        };
+
+        let status = match delta.status() {
+            git2::Delta::Added => "added",
+            git2::Delta::Deleted => "deleted",
+            git2::Delta::Renamed => "renamed",
+            git2::Delta::Copied => "copied",
+            _ => "modified",
+        };
+
+        files.push(ChangedFile {
+            path: new_path.clone().or_else(|| old_path.clone()).unwrap_or_default(),
+            old_path: old_path.filter(|old| Some(old) != new_path.as_ref()),
+            status: status.to_string(),
+            additions,
+            deletions,
+            is_binary,
+        });
+    }
+
+    Ok(files)
+}
+
+/// What a commit changed, diffed against its first parent.
+pub fn show_commit(repo_path: &str, sha: &str) -> Result<CommitDetail, GitError> {
+    let repo = open_repo(repo_path)?;
+    let commit = repo.revparse_single(sha)?.peel_to_commit()?;
+
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    let mut find = git2::DiffFindOptions::new();
+    find.renames(true);
+    diff.find_similar(Some(&mut find))?;
+
+    let committer = commit.committer();
+    Ok(CommitDetail {
+        commit: commit_info(&commit),
+        committer: committer.name().unwrap_or("Unknown").to_string(),
+        committer_email: committer.email().unwrap_or("").to_string(),
+        committer_timestamp: committer.when().seconds(),
+        files: changed_files(&diff)?,
+        diffs: detailed_file_diffs(&diff)?,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareResult {
+    pub base_sha: String,
+    pub head_sha: String,
+    pub merge_base: Option<String>,
+    /// Commits on `head` but not on `base`, newest first.
+    pub head_commits: Vec<Commit>,
+    /// Commits on `base` but not on `head`, newest first.
+    pub base_commits: Vec<Commit>,
+    pub files: Vec<ChangedFile>,
+    /// Hunks for each entry in `files`, only when requested.
+    pub diffs: Option<Vec<DetailedFileDiff>>,
+}
+
+fn commits_between(repo: &Repository, include: git2::Oid, exclude: git2::Oid) -> Result<Vec<Commit>, GitError> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    revwalk.push(include)?;
+    revwalk.hide(exclude)?;
+
+    revwalk
+        .map(|oid| Ok(commit_info(&repo.find_commit(oid?)?)))
+        .collect()
+}
+
+/// Compare two refs the way a pull request does: the file diff runs from
+/// their merge base to `head`, so changes made only on `base` are left out.
+pub fn compare(repo_path: &str, base: &str, head: &str, include_hunks: bool) -> Result<CompareResult, GitError> {
+    let repo = open_repo(repo_path)?;
+    let base_commit = repo.revparse_single(base)?.peel_to_commit()?;
+    let head_commit = repo.revparse_single(head)?.peel_to_commit()?;
+
+    let merge_base = repo.merge_base(base_commit.id(), head_commit.id()).ok();
+    let from_tree = match merge_base {
+        Some(oid) => repo.find_commit(oid)?.tree()?,
+        None => base_commit.tree()?,
+    };
+
+    let mut diff = repo.diff_tree_to_tree(Some(&from_tree), Some(&head_commit.tree()?), None)?;
+    let mut find = git2::DiffFindOptions::new();
+    find.renames(true);
+    diff.find_similar(Some(&mut find))?;
+
+    Ok(CompareResult {
+        base_sha: base_commit.id().to_string(),
+        head_sha: head_commit.id().to_string(),
+        merge_base: merge_base.map(|oid| oid.to_string()),
+        head_commits: commits_between(&repo, head_commit.id(), base_commit.id())?,
+        base_commits: commits_between(&repo, base_commit.id(), head_commit.id())?,
+        files: changed_files(&diff)?,
+        diffs: if include_hunks { Some(detailed_file_diffs(&diff)?) } else { None },
+    })
+}
+
+// Partial Staging
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkSelection {
+    /// Index into `DetailedFileDiff::hunks`.
+    pub hunk_index: usize,
+    /// Indices into the hunk's `lines`. `None` selects the whole hunk.
+    #[serde(default)]
+    pub line_indices: Option<Vec<usize>>,
+}
+
+/// Stage the selected hunks/lines of a diff from `get_unstaged_file_diff`.
+pub fn stage_hunks(
+    repo_path: &str,
+    diff: &DetailedFileDiff,
+    selections: &[HunkSelection],
+) -> Result<(), GitError> {
+    apply_partial_patch_to_index(repo_path, diff, selections, false)
+}
+
+/// Unstage the selected hunks/lines of a staged `DetailedFileDiff`.
+pub fn unstage_hunks(
+    repo_path: &str,
+    diff: &DetailedFileDiff,
+    selections: &[HunkSelection],
+) -> Result<(), GitError> {
+    apply_partial_patch_to_index(repo_path, diff, selections, true)
+}
+
+fn apply_partial_patch_to_index(
+    repo_path: &str,
+    diff: &DetailedFileDiff,
+    selections: &[HunkSelection],
+    reverse: bool,
+) -> Result<(), GitError> {
+    if diff.is_binary {
+        return Err(GitError::Git2(format!(
+            "Cannot partially stage binary file: {}",
+            diff.path
+        )));
+    }
+
+    let repo = open_repo(repo_path)?;
+    let patch = build_partial_patch(diff, selections, reverse, file_mode(&repo, &diff.path)?)?;
+    let patch_diff = git2::Diff::from_buffer(patch.as_bytes())?;
+    repo.apply(&patch_diff, git2::ApplyLocation::Index, None)?;
+
+    Ok(())
+}
+
+/// Mode for the file headers of a partial patch, taken from the index, HEAD
+/// or the working tree, in that order.
+fn file_mode(repo: &Repository, path: &str) -> Result<u32, GitError> {
+    if let Some(entry) = repo.index()?.get_path(Path::new(path), 0) {
+        return Ok(entry.mode);
+    }
+    if let Ok(entry) = repo.head().and_then(|head| head.peel_to_tree()?.get_path(Path::new(path))) {
+        return Ok(entry.filemode() as u32);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = std::fs::metadata(repo_workdir(repo)?.join(path)) {
+            if metadata.permissions().mode() & 0o111 != 0 {
+                return Ok(0o100755);
+            }
+        }
+    }
+    Ok(0o100644)
+}
+
+/// Build a unified diff containing only the selected hunks/lines.
+///
+/// Unselected additions are dropped and unselected deletions become context, so
+/// the patch applies cleanly on top of the pre-image. With `reverse` the diff is
+/// inverted first, which turns a staged HEAD->index diff into an unstage patch.
+fn build_partial_patch(
+    diff: &DetailedFileDiff,
+    selections: &[HunkSelection],
+    reverse: bool,
+    mode: u32,
+) -> Result<String, GitError> {
+    for selection in selections {
+        let hunk = diff.hunks.get(selection.hunk_index).ok_or_else(|| {
+            GitError::Git2(format!("Hunk {} does not exist in {}", selection.hunk_index, diff.path))
+        })?;
+        let bad_line = selection
+            .line_indices
+            .iter()
+            .flatten()
+            .find(|&&line_index| line_index >= hunk.lines.len());
+        if let Some(line_index) = bad_line {
+            return Err(GitError::Git2(format!(
+                "Line {} does not exist in hunk {} of {}",
+                line_index, selection.hunk_index, diff.path
+            )));
+        }
+    }
+
+    let mut hunks = String::new();
+    let mut offset: i64 = 0;
+    let mut emitted = false;
+    let mut all_selected = true;
+
+    for (hunk_index, hunk) in diff.hunks.iter().enumerate() {
+        let Some(selection) = selections.iter().find(|s| s.hunk_index == hunk_index) else {
+            all_selected &= hunk.lines.iter().all(|line| line.line_type == "context");
+            continue;
+        };
+
+        let mut body = String::new();
+        let mut old_count: i64 = 0;
+        let mut new_count: i64 = 0;
+        let mut changed = false;
+
+        for (line_index, line) in hunk.lines.iter().enumerate() {
+            // "\ No newline at end of file" markers carry no line numbers; they
+            // are re-emitted below based on the preceding line's content.
+            if line.line_type == "context"
+                && line.old_line_number.is_none()
+                && line.new_line_number.is_none()
+            {
+                continue;
+            }
+
+            let selected = selection
+                .line_indices
+                .as_ref()
+                .is_none_or(|indices| indices.contains(&line_index));
+            all_selected &= selected || line.line_type == "context";
+
+            let line_type = match (line.line_type.as_str(), reverse) {
+                ("add", true) => "delete",
+                ("delete", true) => "add",
+                (other, _) => other,
+            };
+
+            let prefix = match (line_type, selected) {
+                ("add", true) => '+',
+                ("add", false) => continue,
+                ("delete", true) => '-',
+                _ => ' ',
+            };
+
+            match prefix {
+                '+' => new_count += 1,
+                '-' => old_count += 1,
+                _ => {
+                    old_count += 1;
+                    new_count += 1;
+                }
+            }
+            changed |= prefix != ' ';
+
+            body.push(prefix);
+            body.push_str(&line.content);
+            if !line.content.ends_with('\n') {
+                body.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+
+        if !changed {
+            continue;
+        }
+
+        let old_start = i64::from(if reverse { hunk.new_start } else { hunk.old_start });
+        let new_start = if new_count == 0 {
+            old_start + offset - 1
+        } else if old_count == 0 {
+            old_start + offset + 1
+        } else {
+            old_start + offset
+        };
+
+        hunks.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start,
+            old_count,
+            new_start.max(0),
+            new_count
+        ));
+        hunks.push_str(&body);
+
+        offset += new_count - old_count;
+        emitted = true;
+    }
+
+    if !emitted {
+        return Err(GitError::Git2("No changes selected".to_string()));
+    }
+
+    let old_path = diff.old_path.as_deref().unwrap_or(&diff.path);
+    let (from_path, to_path) = if reverse {
+        (diff.path.as_str(), old_path)
+    } else {
+        (old_path, diff.path.as_str())
+    };
+    // The file only disappears when every line of the deletion is applied
+    let (from_missing, to_missing) = if reverse {
+        (diff.is_deleted, diff.is_new && all_selected)
+    } else {
+        (diff.is_new, diff.is_deleted && all_selected)
+    };
+
+    let mut patch = format!("diff --git a/{} b/{}\n", from_path, to_path);
+    if from_missing {
+        patch.push_str(&format!("new file mode {:o}\n--- /dev/null\n", mode));
+    } else if to_missing {
+        patch.push_str(&format!("deleted file mode {:o}\n--- a/{}\n", mode, from_path));
+    } else {
+        patch.push_str(&format!("--- a/{}\n", from_path));
+    }
+    if to_missing {
+        patch.push_str("+++ /dev/null\n");
+    } else {
+        patch.push_str(&format!("+++ b/{}\n", to_path));
+    }
+    patch.push_str(&hunks);
+
+    Ok(patch)
+}
+
+// Discarding Changes
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DiscardTarget {
+    /// Revert the working tree to the staged version.
+    Index,
+    /// Revert both the index and the working tree to HEAD.
+    Head,
+}
+
+/// Snapshot of a file taken before it was discarded. The contents are written
+/// to the object database as dangling blobs so they survive until the next gc.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscardBackup {
+    pub path: String,
+    pub worktree_sha: Option<String>,
+    pub index_sha: Option<String>,
+    pub mode: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscardResult {
+    pub backups: Vec<DiscardBackup>,
+}
+
+fn repo_workdir(repo: &Repository) -> Result<&Path, GitError> {
+    repo.workdir()
+        .ok_or_else(|| GitError::Git2("Repository has no working directory".to_string()))
+}
+
+fn backup_worktree_file(repo: &Repository, file: &str) -> Result<DiscardBackup, GitError> {
+    let full_path = repo_workdir(repo)?.join(file);
+    let worktree_sha = if full_path.is_file() {
+        Some(repo.blob_path(&full_path)?.to_string())
+    } else {
+        None
+    };
+
+    let index = repo.index()?;
+    let (index_sha, mode) = match index.get_path(Path::new(file), 0) {
+        Some(entry) => (Some(entry.id.to_string()), entry.mode),
+        None => (None, 0o100644),
+    };
+
+    Ok(DiscardBackup {
+        path: file.to_string(),
+        worktree_sha,
+        index_sha,
+        mode,
+    })
+}
+
+/// Discard working-tree changes for whole files. Files unknown to the target
+/// are removed from the working tree.
+pub fn discard_files(
+    repo_path: &str,
+    files: &[String],
+    target: DiscardTarget,
+) -> Result<DiscardResult, GitError> {
+    let repo = open_repo(repo_path)?;
+    let backups = files
+        .iter()
+        .map(|file| backup_worktree_file(&repo, file))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if target == DiscardTarget::Head {
+        let paths: Vec<&Path> = files.iter().map(|f| Path::new(f.as_str())).collect();
+        match repo.head().and_then(|head| head.peel_to_commit()) {
+            Ok(head) => repo.reset_default(Some(head.as_object()), &paths)?,
+            Err(_) => {
+                let mut index = repo.index()?;
+                for path in &paths {
+                    let _ = index.remove_path(path);
+                }
+                index.write()?;
+            }
+        }
+    }
+
+    let workdir = repo_workdir(&repo)?;
+    let mut index = repo.index()?;
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.force();
+    let mut has_checkout_paths = false;
+
+    for file in files {
+        if index.get_path(Path::new(file), 0).is_some() {
+            checkout.path(file);
+            has_checkout_paths = true;
+        } else {
+            let full_path = workdir.join(file);
+            if full_path.is_file() {
+                std::fs::remove_file(full_path)?;
+            }
+        }
+    }
+
+    if has_checkout_paths {
+        repo.checkout_index(Some(&mut index), Some(&mut checkout))?;
+    }
+
+    Ok(DiscardResult { backups })
+}
+
+/// Discard the selected hunks/lines of a `DetailedFileDiff`.
+///
+/// For `DiscardTarget::Index` the diff comes from `get_unstaged_file_diff` and
+/// only the working tree changes. For `DiscardTarget::Head` it is the unstaged
+/// one from `get_file_diff`; staged hunks overlapping the selection are
+/// reverted in the index as a whole, the working tree only loses the selected
+/// lines.
+pub fn discard_hunks(
+    repo_path: &str,
+    diff: &DetailedFileDiff,
+    selections: &[HunkSelection],
+    target: DiscardTarget,
+) -> Result<DiscardResult, GitError> {
+    if diff.is_binary {
+        return Err(GitError::Git2(format!(
+            "Cannot partially discard binary file: {}",
+            diff.path
+        )));
+    }
+
+    let repo = open_repo(repo_path)?;
+    let mode = file_mode(&repo, &diff.path)?;
+    let workdir_patch = build_partial_patch(diff, selections, true, mode)?;
+    let mut patches = vec![(workdir_patch, git2::ApplyLocation::WorkDir)];
+
+    if target == DiscardTarget::Head {
+        let staged = get_file_diff(repo_path, &diff.path, true)?;
+        let staged_selections = overlapping_hunks(&staged, diff, selections);
+        if !staged_selections.is_empty() {
+            let index_patch = build_partial_patch(&staged, &staged_selections, true, mode)?;
+            patches.push((index_patch, git2::ApplyLocation::Index));
+        }
+    }
+
+    // Check every patch before touching anything so a failure leaves no half-discard
+    let patches = patches
+        .into_iter()
+        .map(|(patch, location)| Ok((git2::Diff::from_buffer(patch.as_bytes())?, location)))
+        .collect::<Result<Vec<_>, GitError>>()?;
+    for (patch, location) in &patches {
+        let mut check = git2::ApplyOptions::new();
+        check.check(true);
+        repo.apply(patch, *location, Some(&mut check))?;
+    }
+
+    let backup = backup_worktree_file(&repo, &diff.path)?;
+    for (patch, location) in &patches {
+        repo.apply(patch, *location, None)?;
+    }
+
+    Ok(DiscardResult {
+        backups: vec![backup],
+    })
+}
+
+/// Staged hunks whose HEAD line range touches a selected hunk of `diff`. Both
+/// diffs start from HEAD, so their old-side line numbers line up.
+fn overlapping_hunks(
+    staged: &DetailedFileDiff,
+    diff: &DetailedFileDiff,
+    selections: &[HunkSelection],
+) -> Vec<HunkSelection> {
+    let old_range = |hunk: &DetailedDiffHunk| (hunk.old_start, hunk.old_start + hunk.old_lines);
+    let selected: Vec<(u32, u32)> = selections
+        .iter()
+        .filter_map(|selection| diff.hunks.get(selection.hunk_index))
+        .map(old_range)
+        .collect();
+
+    staged
+        .hunks
+        .iter()
+        .enumerate()
+        .filter(|(_, hunk)| {
+            let (start, end) = old_range(hunk);
+            selected.iter().any(|&(sel_start, sel_end)| start <= sel_end && sel_start <= end)
+        })
+        .map(|(hunk_index, _)| HunkSelection { hunk_index, line_indices: None })
+        .collect()
+}
+
+/// Put discarded files back exactly as they were captured in the backups.
+pub fn restore_discard_backups(repo_path: &str, backups: &[DiscardBackup]) -> Result<(), GitError> {
+    let repo = open_repo(repo_path)?;
+    let workdir = repo_workdir(&repo)?;
+    let mut index = repo.index()?;
+
+    for backup in backups {
+        let full_path = workdir.join(&backup.path);
+        match &backup.worktree_sha {
+            Some(sha) => {
+                let blob = repo.find_blob(git2::Oid::from_str(sha)?)?;
+                if let Some(parent) = full_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&full_path, blob.content())?;
+            }
+            None => {
+                if full_path.is_file() {
+                    std::fs::remove_file(&full_path)?;
+                }
+            }
+        }
+
+        match &backup.index_sha {
+            Some(sha) => {
+                let oid = git2::Oid::from_str(sha)?;
+                let blob = repo.find_blob(oid)?;
+                index.add(&git2::IndexEntry {
+                    ctime: git2::IndexTime::new(0, 0),
+                    mtime: git2::IndexTime::new(0, 0),
+                    dev: 0,
+                    ino: 0,
+                    mode: backup.mode,
+                    uid: 0,
+                    gid: 0,
+                    file_size: blob.size() as u32,
+                    id: oid,
+                    flags: 0,
+                    flags_extended: 0,
+                    path: backup.path.as_bytes().to_vec(),
+                })?;
+            }
+            None => {
+                let _ = index.remove_path(Path::new(&backup.path));
+            }
+        }
+    }
+
+    index.write()?;
+    Ok(())
+}
+
+// Conflict Resolution
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RepoOperation {
+    None,
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+    Bisect,
+    ApplyMailbox,
+}
+
+impl From<git2::RepositoryState> for RepoOperation {
+    fn from(state: git2::RepositoryState) -> Self {
+        use git2::RepositoryState as State;
+        match state {
+            State::Clean => RepoOperation::None,
+            State::Merge => RepoOperation::Merge,
+            State::Rebase | State::RebaseInteractive | State::RebaseMerge => RepoOperation::Rebase,
+            State::CherryPick | State::CherryPickSequence => RepoOperation::CherryPick,
+            State::Revert | State::RevertSequence => RepoOperation::Revert,
+            State::Bisect => RepoOperation::Bisect,
+            State::ApplyMailbox | State::ApplyMailboxOrRebase => RepoOperation::ApplyMailbox,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictEntry {
+    pub path: String,
+    pub has_base: bool,
+    pub has_ours: bool,
+    pub has_theirs: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictState {
+    pub operation: RepoOperation,
+    pub conflicts: Vec<ConflictEntry>,
+}
+
+/// The three sides of a conflicted file. During a rebase git swaps the
+/// meaning of the sides: "ours" is the upstream being rebased onto.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictDetail {
+    pub path: String,
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+    /// Working-tree contents, including git's conflict markers.
+    pub merged: Option<String>,
+    pub is_binary: bool,
+}
+
+fn index_entry_path(entry: &git2::IndexEntry) -> String {
+    String::from_utf8_lossy(&entry.path).to_string()
+}
+
+pub fn list_conflicts(repo_path: &str) -> Result<ConflictState, GitError> {
+    let repo = open_repo(repo_path)?;
+    let index = repo.index()?;
+
+    let mut conflicts = Vec::new();
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let path = [&conflict.our, &conflict.their, &conflict.ancestor]
+            .iter()
+            .find_map(|entry| entry.as_ref().map(index_entry_path))
+            .unwrap_or_default();
+
+        conflicts.push(ConflictEntry {
+            path,
+            has_base: conflict.ancestor.is_some(),
+            has_ours: conflict.our.is_some(),
+            has_theirs: conflict.their.is_some(),
+        });
+    }
+
+    Ok(ConflictState {
+        operation: repo.state().into(),
+        conflicts,
+    })
+}
+
+pub fn get_conflict_detail(repo_path: &str, file_path: &str) -> Result<ConflictDetail, GitError> {
+    let repo = open_repo(repo_path)?;
+    let index = repo.index()?;
+
+    let conflict = index
+        .conflicts()?
+        .filter_map(Result::ok)
+        .find(|conflict| {
+            [&conflict.our, &conflict.their, &conflict.ancestor]
+                .iter()
+                .any(|entry| entry.as_ref().is_some_and(|e| index_entry_path(e) == file_path))
+        })
+        .ok_or_else(|| GitError::Git2(format!("No conflict for path: {}", file_path)))?;
+
+    let mut is_binary = false;
+    let mut read_side = |entry: &Option<git2::IndexEntry>| -> Result<Option<String>, GitError> {
+        match entry {
+            Some(entry) => {
+                let blob = repo.find_blob(entry.id)?;
+                is_binary |= blob.is_binary();
+                Ok(Some(String::from_utf8_lossy(blob.content()).to_string()))
+            }
+            None => Ok(None),
+        }
+    };
+
+    let base = read_side(&conflict.ancestor)?;
+    let ours = read_side(&conflict.our)?;
+    let theirs = read_side(&conflict.their)?;
+
+    let merged = std::fs::read(repo_workdir(&repo)?.join(file_path))
+        .ok()
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string());
+
+    Ok(ConflictDetail {
+        path: file_path.to_string(),
+        base: if is_binary { None } else { base },
+        ours: if is_binary { None } else { ours },
+        theirs: if is_binary { None } else { theirs },
+        merged: if is_binary { None } else { merged },
+        is_binary,
+    })
+}
+
+/// Mark a conflicted file as resolved. When `content` is given it replaces the
+/// working-tree file first; a file missing from the working tree resolves the
+/// conflict as a deletion.
+pub fn resolve_conflict(
+    repo_path: &str,
+    file_path: &str,
+    content: Option<String>,
+) -> Result<(), GitError> {
+    let repo = open_repo(repo_path)?;
+    let full_path = repo_workdir(&repo)?.join(file_path);
+
+    if let Some(content) = content {
+        std::fs::write(&full_path, content)?;
+    }
+
+    let mut index = repo.index()?;
+    if full_path.exists() {
+        index.add_path(Path::new(file_path))?;
+    } else {
+        index.remove_path(Path::new(file_path))?;
+    }
+    index.write()?;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OperationAction {
+    Continue,
+    Skip,
+    Abort,
+}
+
+pub fn continue_operation(repo_path: &str) -> Result<ConflictState, GitError> {
+    run_operation_action(repo_path, OperationAction::Continue)
+}
+
+pub fn skip_operation(repo_path: &str) -> Result<ConflictState, GitError> {
+    run_operation_action(repo_path, OperationAction::Skip)
+}
+
+pub fn abort_operation(repo_path: &str) -> Result<ConflictState, GitError> {
+    run_operation_action(repo_path, OperationAction::Abort)
+}
+
+fn run_operation_action(repo_path: &str, action: OperationAction) -> Result<ConflictState, GitError> {
+    let (operation, revert_base): (RepoOperation, Option<git2::Oid>) = {
+        let repo = open_repo(repo_path)?;
+        if action == OperationAction::Continue && repo.index()?.has_conflicts() {
+            return Err(operation_conflict_error(repo_path, repo.state().into()));
+        }
+        let operation = repo.state().into();
+        // A revert sequence remembers where it started; a single revert
+        // commits on top of the current HEAD
+        let revert_base = match operation {
+            RepoOperation::Revert => match std::fs::read_to_string(repo.path().join("sequencer").join("head")) {
+                Ok(sha) => Some(git2::Oid::from_str(sha.trim())?),
+                Err(_) => Some(repo.head()?.peel_to_commit()?.id()),
+            },
+            _ => None,
+        };
+        (operation, revert_base)
+    };
+
+    let flag = match action {
+        OperationAction::Continue => "--continue",
+        OperationAction::Skip => "--skip",
+        OperationAction::Abort => "--abort",
+    };
+
+    let args: Vec<&str> = match (operation, action) {
+        (RepoOperation::Rebase, _) => vec!["rebase", flag],
+        (RepoOperation::CherryPick, _) => vec!["cherry-pick", flag],
+        (RepoOperation::Revert, _) => vec!["revert", flag],
+        (RepoOperation::ApplyMailbox, _) => vec!["am", flag],
+        (RepoOperation::Merge, OperationAction::Continue) => vec!["commit", "--no-edit"],
+        (RepoOperation::Merge, OperationAction::Abort) => vec!["merge", "--abort"],
+        (RepoOperation::Merge, OperationAction::Skip) => {
+            return Err(GitError::Git2("A merge cannot be skipped".to_string()));
+        }
+        (RepoOperation::None, _) | (RepoOperation::Bisect, _) => {
+            return Err(GitError::Git2("No merge or rebase in progress".to_string()));
+        }
+    };
+
+    let output = std::process::Command::new("git")
+        .args(&args)
+        .env("GIT_EDITOR", "true")
+        .stdin(std::process::Stdio::null())
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| GitError::Io(format!("Failed to run git {}: {}", args.join(" "), e)))?;
+
+    if operation == RepoOperation::Rebase {
+        remove_rebase_work_dir(repo_path);
+    }
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        eprintln!("git {} failed: {}", args.join(" "), stderr);
+
+        let state = list_conflicts(repo_path)?;
+        if !state.conflicts.is_empty() {
+            return Err(operation_conflict_error(repo_path, state.operation));
+        }
+        if operation == RepoOperation::Rebase {
+            return Err(local_rebase_error(&format!("{}\n{}", stdout, stderr)));
+        }
+        return Err(GitError::Git2(stderr.trim().to_string()));
+    }
+
+    let state = list_conflicts(repo_path)?;
+    if let Some(base) = revert_base {
+        if action != OperationAction::Abort && state.operation == RepoOperation::None {
+            add_revert_trailers(repo_path, base)?;
+        }
+    }
+    Ok(state)
+}
+
+fn operation_conflict_error(repo_path: &str, operation: RepoOperation) -> GitError {
+    match operation {
+        RepoOperation::Rebase => GitError::RebaseConflict(
+            "Rebase paused on conflicts. Resolve the conflicted files, then continue, skip or abort.".to_string(),
+        ),
+        RepoOperation::CherryPick | RepoOperation::Revert => {
+            let stopped_at = if operation == RepoOperation::CherryPick {
+                "CHERRY_PICK_HEAD"
+            } else {
+                "REVERT_HEAD"
+            };
+            let sha = open_repo(repo_path)
+                .and_then(|repo| Ok(repo.revparse_single(stopped_at)?.id().to_string()))
+                .unwrap_or_default();
+            let conflicts = list_conflicts(repo_path)
+                .map(|state| state.conflicts.into_iter().map(|c| c.path).collect())
+                .unwrap_or_default();
+            if operation == RepoOperation::CherryPick {
+                GitError::CherryPickConflict { sha, conflicts }
+            } else {
+                GitError::RevertConflict { sha, conflicts }
+            }
+        }
+        _ => GitError::MergeConflict,
+    }
+}
+
+// Cherry-pick and Revert
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PickOptions {
+    /// 1-based parent to treat as the mainline when a commit is a merge.
+    pub mainline: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PickResult {
+    pub head_sha: String,
+    /// Commits created on the current branch, newest first.
+    pub commits: Vec<Commit>,
+}
+
+/// Apply `shas` in order on top of the current branch. Each commit records
+/// where it was picked from.
+pub fn cherry_pick(repo_path: &str, shas: &[String], options: &PickOptions) -> Result<PickResult, GitError> {
+    run_pick_sequence(repo_path, RepoOperation::CherryPick, shas, options)
+}
+
+/// Create commits undoing `shas`, in the order given.
+pub fn revert(repo_path: &str, shas: &[String], options: &PickOptions) -> Result<PickResult, GitError> {
+    run_pick_sequence(repo_path, RepoOperation::Revert, shas, options)
+}
+
+fn run_pick_sequence(
+    repo_path: &str,
+    operation: RepoOperation,
+    shas: &[String],
+    options: &PickOptions,
+) -> Result<PickResult, GitError> {
+    if shas.is_empty() {
+        return Err(GitError::Git2("No commits selected".to_string()));
+    }
+
+    let (old_head, oids) = {
+        let repo = open_repo(repo_path)?;
+        if repo.state() != git2::RepositoryState::Clean {
+            return Err(GitError::Git2(
+                "Finish or abort the operation in progress first".to_string(),
+            ));
+        }
+        let old_head = repo.head()?.peel_to_commit()?.id();
+
+        let mut oids = Vec::new();
+        for sha in shas {
+            let commit = repo.revparse_single(sha)?.peel_to_commit()?;
+            match options.mainline {
+                None if commit.parent_count() > 1 => {
+                    return Err(GitError::Git2(format!(
+                        "{} is a merge commit; choose which parent is the mainline",
+                        &commit.id().to_string()[..7]
+                    )));
+                }
+                Some(_) if commit.parent_count() < 2 => {
+                    return Err(GitError::Git2(format!(
+                        "{} is not a merge commit; pick merges and other commits separately",
+                        &commit.id().to_string()[..7]
+                    )));
+                }
+                Some(mainline) if mainline == 0 || mainline as usize > commit.parent_count() => {
+                    return Err(GitError::Git2(format!(
+                        "{} has no parent number {}",
+                        &commit.id().to_string()[..7],
+                        mainline
+                    )));
+                }
+                _ => {}
+            }
+            oids.push(commit.id().to_string());
+        }
+        (old_head, oids)
+    };
+
+    let mainline = options.mainline.map(|mainline| mainline.to_string());
+    let mut args: Vec<&str> = match operation {
+        RepoOperation::Revert => vec!["revert", "--no-edit"],
+        _ => vec!["cherry-pick", "-x"],
+    };
+    if let Some(mainline) = &mainline {
+        args.extend(["-m", mainline.as_str()]);
+    }
+    args.extend(oids.iter().map(String::as_str));
+
+    let output = std::process::Command::new("git")
+        .args(&args)
+        .env("GIT_EDITOR", "true")
+        .stdin(std::process::Stdio::null())
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| GitError::Io(format!("Failed to run git {}: {}", args[0], e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        eprintln!("git {} failed: {}", args[0], stderr);
+
+        let state = list_conflicts(repo_path)?;
+        if !state.conflicts.is_empty() {
+            return Err(operation_conflict_error(repo_path, state.operation));
+        }
+        return Err(GitError::Git2(stderr.trim().to_string()));
+    }
+
+    if operation == RepoOperation::Revert {
+        add_revert_trailers(repo_path, old_head)?;
+    }
+
+    let repo = open_repo(repo_path)?;
+    let head = repo.head()?.peel_to_commit()?;
+    let commits = commits_between(&repo, head.id(), old_head)?;
+
+    Ok(PickResult {
+        head_sha: head.id().to_string(),
+        commits,
+    })
+}
+
+/// Rewrite the revert commits made on top of `base` so each ends with a
+/// `Reverts: <sha>` trailer taken from git's "This reverts commit" line.
+fn add_revert_trailers(repo_path: &str, base: git2::Oid) -> Result<(), GitError> {
+    let repo = open_repo(repo_path)?;
+
+    let mut created = Vec::new();
+    let mut commit = repo.head()?.peel_to_commit()?;
+    while commit.id() != base {
+        if commit.parent_count() != 1 {
+            // Not a plain run of reverts; leave the history alone
+            return Ok(());
+        }
+        let parent = commit.parent(0)?;
+        created.push(commit);
+        commit = parent;
+    }
+
+    let mut parent = commit;
+    let mut rewritten = false;
+    for commit in created.into_iter().rev() {
+        let message = commit.message().unwrap_or("");
+        let trailered = with_revert_trailer(message);
+        if trailered.is_none() && !rewritten {
+            parent = commit;
+            continue;
+        }
+
+        let oid = write_commit(
+            &repo,
+            &commit.author(),
+            &commit.committer(),
+            trailered.as_deref().unwrap_or(message),
+            &commit.tree()?,
+            &[&parent],
+        )?;
+        parent = repo.find_commit(oid)?;
+        rewritten = true;
+    }
+
+    if rewritten {
+        advance_head(&repo, parent.id(), "revert")?;
+    }
+    Ok(())
+}
+
+/// `message` with a `Reverts: <sha>` trailer, or `None` when it is not a
+/// revert or already has one.
+fn with_revert_trailer(message: &str) -> Option<String> {
+    let sha = message
+        .lines()
+        .find_map(|line| line.strip_prefix("This reverts commit "))?
+        .trim_end_matches(|c: char| c == '.' || c.is_whitespace())
+        .split(',')
+        .next()?
+        .to_string();
+    let trailer = format!("Reverts: {}", sha);
+    if message.lines().any(|line| line.trim() == trailer) {
+        return None;
+    }
+
+    let body = message.trim_end();
+    let has_trailers = git2::message_trailers_strs(body).is_ok_and(|trailers| trailers.len() > 0);
+    let separator = if has_trailers { "\n" } else { "\n\n" };
+    Some(format!("{}{}{}\n", body, separator, trailer))
+}
+
+// Interactive Rebase
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RebaseAction {
+    Pick,
+    Reword,
+    Squash,
+    Fixup,
+    Drop,
+}
+
+impl RebaseAction {
+    fn todo_command(self) -> &'static str {
+        match self {
+            // Rewords are applied with an exec step so no editor is needed
+            RebaseAction::Pick | RebaseAction::Reword => "pick",
+            RebaseAction::Squash => "squash",
+            RebaseAction::Fixup => "fixup",
+            RebaseAction::Drop => "drop",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RebaseStep {
+    pub action: RebaseAction,
+    pub commit: Commit,
+    /// Replaces the message of the commit produced by this step. On the last
+    /// squash or fixup of a group it rewords the combined commit.
+    #[serde(default)]
+    pub new_message: Option<String>,
+}
+
+/// Todo list for the commits ahead of upstream, oldest first.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RebasePlan {
+    pub branch: String,
+    /// Merge base with upstream; the rewritten commits are replayed on top of it.
+    pub onto: String,
+    pub steps: Vec<RebaseStep>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebaseResult {
+    pub head_sha: String,
+    pub commits: usize,
+}
+
+pub fn plan_rebase(repo_path: &str) -> Result<RebasePlan, GitError> {
+    let repo = open_repo(repo_path)?;
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Err(GitError::Git2("Cannot rebase while HEAD is detached".to_string()));
+    }
+
+    let branch_name = head.shorthand().unwrap_or("").to_string();
+    let local = repo.find_branch(&branch_name, git2::BranchType::Local)?;
+    let upstream = local.upstream().map_err(|_| GitError::NoRemote)?;
+
+    let head_oid = head.peel_to_commit()?.id();
+    let upstream_oid = upstream.get().peel_to_commit()?.id();
+    let onto = repo.merge_base(head_oid, upstream_oid)?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+    revwalk.push(head_oid)?;
+    revwalk.hide(upstream_oid)?;
+
+    let mut steps = Vec::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() > 1 {
+            return Err(GitError::Git2(
+                "Unpushed commits include a merge, which cannot be rebased interactively".to_string(),
+            ));
+        }
+
+        steps.push(RebaseStep {
+            action: RebaseAction::Pick,
+            commit: commit_info(&commit),
+            new_message: None,
+        });
+    }
+
+    Ok(RebasePlan {
+        branch: branch_name,
+        onto: onto.to_string(),
+        steps,
+    })
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Holds the todo list and reworded messages of `execute_rebase` while the
+/// rebase runs, since a paused rebase reads them again on continue.
+const REBASE_WORK_DIR: &str = "vibogit-rebase";
+
+/// Drop the `execute_rebase` files once no rebase is left to read them.
+fn remove_rebase_work_dir(repo_path: &str) {
+    let Ok(repo) = open_repo(repo_path) else {
+        return;
+    };
+    if RepoOperation::from(repo.state()) != RepoOperation::Rebase {
+        let _ = std::fs::remove_dir_all(repo.path().join(REBASE_WORK_DIR));
+    }
+}
+
+/// Run an edited plan through `git rebase -i`. A conflict leaves the rebase
+/// paused for the conflict resolution flow.
+pub fn execute_rebase(repo_path: &str, plan: &RebasePlan) -> Result<RebaseResult, GitError> {
+    // Only the commits from a fresh plan may be rewritten, each exactly once
+    let current = plan_rebase(repo_path)?;
+    let mut expected: Vec<&str> = current.steps.iter().map(|s| s.commit.sha.as_str()).collect();
+    let mut planned: Vec<&str> = plan.steps.iter().map(|s| s.commit.sha.as_str()).collect();
+    expected.sort_unstable();
+    planned.sort_unstable();
+    if current.branch != plan.branch || current.onto != plan.onto || expected != planned {
+        return Err(GitError::Git2(
+            "Unpushed commits have changed since this plan was made; plan the rebase again".to_string(),
+        ));
+    }
+
+    let first_kept = plan.steps.iter().find(|s| s.action != RebaseAction::Drop);
+    if first_kept.is_some_and(|s| matches!(s.action, RebaseAction::Squash | RebaseAction::Fixup)) {
+        return Err(GitError::Git2(
+            "The first commit in a rebase cannot be squashed or fixed up".to_string(),
+        ));
+    }
+
+    let work_dir = open_repo(repo_path)?.path().join(REBASE_WORK_DIR);
+    if work_dir.exists() {
+        std::fs::remove_dir_all(&work_dir)?;
+    }
+    std::fs::create_dir_all(&work_dir)?;
+
+    let mut todo = String::new();
+    for (i, step) in plan.steps.iter().enumerate() {
+        todo.push_str(&format!("{} {}\n", step.action.todo_command(), step.commit.sha));
+        if step.action == RebaseAction::Drop {
+            continue;
+        }
+        if let Some(message) = &step.new_message {
+            let message_path = work_dir.join(format!("message-{}", i));
+            std::fs::write(&message_path, message)?;
+            todo.push_str(&format!(
+                "exec git commit --amend --allow-empty --quiet -F {}\n",
+                shell_quote(&message_path.to_string_lossy())
+            ));
+        }
+    }
+    let todo_path = work_dir.join("git-rebase-todo");
+    std::fs::write(&todo_path, todo)?;
+
+    let output = std::process::Command::new("git")
+        .args(["rebase", "-i", &plan.onto])
+        .env("GIT_SEQUENCE_EDITOR", format!("cp {}", shell_quote(&todo_path.to_string_lossy())))
+        .env("GIT_EDITOR", "true")
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| GitError::Io(format!("Failed to run git rebase: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        eprintln!("git rebase -i failed: {}", stderr);
+
+        let state = list_conflicts(repo_path)?;
+        if !state.conflicts.is_empty() {
+            return Err(operation_conflict_error(repo_path, state.operation));
+        }
+        remove_rebase_work_dir(repo_path);
+        return Err(local_rebase_error(&format!("{}\n{}", stdout, stderr)));
+    }
+
+    remove_rebase_work_dir(repo_path);
+
+    let repo = open_repo(repo_path)?;
+    let head_sha = repo.head()?.peel_to_commit()?.id();
+    let (commits, _behind, _has_remote) = get_ahead_behind(&repo, &plan.branch)?;
+
+    Ok(RebaseResult {
+        head_sha: head_sha.to_string(),
+        commits,
+    })
+}
+
+// Tags and Releases
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub name: String,
+    pub target_sha: String,
+    pub annotated: bool,
+    pub message: Option<String>,
+    pub tagger: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum VersionBump {
+    Major,
+    Minor,
+    Patch,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseOptions {
+    pub bump: VersionBump,
+    /// Push the release commit and its tag once created.
+    #[serde(default)]
+    pub ship: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseResult {
+    pub previous_version: String,
+    pub version: String,
+    pub tag: String,
+    pub sha: String,
+    pub files: Vec<String>,
+    pub shipped: Option<ShipResult>,
+}
+
+pub fn list_tags(repo_path: &str) -> Result<Vec<Tag>, GitError> {
+    let repo = open_repo(repo_path)?;
+
+    let mut tag_refs = Vec::new();
+    repo.tag_foreach(|oid, name| {
+        let name = String::from_utf8_lossy(name);
+        tag_refs.push((oid, name.trim_start_matches("refs/tags/").to_string()));
+        true
+    })?;
+
+    let mut tags = Vec::new();
+    for (oid, name) in tag_refs {
+        let tag = match repo.find_tag(oid) {
+            Ok(annotation) => Tag {
+                name,
+                target_sha: annotation.target()?.peel(git2::ObjectType::Commit)?.id().to_string(),
+                annotated: true,
+                message: annotation.message().map(|m| m.to_string()),
+                tagger: annotation.tagger().and_then(|t| t.name().map(|n| n.to_string())),
+                timestamp: annotation.tagger().map(|t| t.when().seconds()),
+            },
+            Err(_) => Tag {
+                name,
+                target_sha: repo.find_object(oid, None)?.peel(git2::ObjectType::Commit)?.id().to_string(),
+                annotated: false,
+                message: None,
+                tagger: None,
+                timestamp: None,
+            },
+        };
+        tags.push(tag);
+    }
+
+    tags.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(tags)
+}
+
+/// Tag `target` (HEAD when `None`). A message makes the tag annotated.
+pub fn create_tag(
+    repo_path: &str,
+    name: &str,
+    target: Option<&str>,
+    message: Option<&str>,
+) -> Result<Tag, GitError> {
+    let repo = open_repo(repo_path)?;
+    let object = repo.revparse_single(target.unwrap_or("HEAD"))?.peel(git2::ObjectType::Commit)?;
+
+    match message {
+        Some(message) => {
+            let sig = repo.signature().unwrap_or_else(|_| {
+                Signature::now("ViboGit User", "user@vibogit.app").unwrap()
+            });
+            repo.tag(name, &object, &sig, message, false)?;
+        }
+        None => {
+            repo.tag_lightweight(name, &object, false)?;
+        }
+    }
+
+    list_tags(repo_path)?
+        .into_iter()
+        .find(|tag| tag.name == name)
+        .ok_or_else(|| GitError::Git2(format!("Tag '{}' was not created", name)))
+}
+
+/// Delete a tag locally and, when `remote` is given, on that remote first.
+pub fn delete_tag(repo_path: &str, name: &str, remote: Option<&str>) -> Result<(), GitError> {
+    if let Some(remote) = remote {
+        let output = std::process::Command::new("git")
+            .args(["push", remote, "--delete", &format!("refs/tags/{}", name)])
+            .current_dir(repo_path)
+            .output()
+            .map_err(|e| GitError::Io(format!("Failed to run git push --delete: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            eprintln!("Git push --delete failed: {}", stderr);
+            return Err(classify_push_error(&stderr, false));
+        }
+    }
+
+    let repo = open_repo(repo_path)?;
+    repo.tag_delete(name)?;
+    Ok(())
+}
+
+/// Push one tag, or every tag when `name` is `None`. The remote defaults to
+/// the current branch's upstream remote.
+pub fn push_tags(repo_path: &str, remote: Option<&str>, name: Option<&str>) -> Result<(), GitError> {
+    let remote = {
+        let repo = open_repo(repo_path)?;
+        let branch_name = repo
+            .head()
+            .ok()
+            .and_then(|head| head.shorthand().map(|name| name.to_string()))
+            .unwrap_or_default();
+        resolve_remote(&repo, &branch_name, remote)?
+    };
+
+    let refspec = match name {
+        Some(name) => format!("refs/tags/{}", name),
+        None => "--tags".to_string(),
+    };
+
+    let output = std::process::Command::new("git")
+        .args(["push", &remote, &refspec])
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| GitError::Io(format!("Failed to run git push: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        eprintln!("Git push of tags failed: {}", stderr);
+        return Err(classify_push_error(&stderr, false));
+    }
+
+    Ok(())
+}
+
+/// Manifests that carry the project version, relative to the repository root.
+const VERSION_FILES: [&str; 5] = [
+    "package.json",
+    "Cargo.toml",
+    "tauri.conf.json",
+    "src-tauri/Cargo.toml",
+    "src-tauri/tauri.conf.json",
+];
+
+fn is_json_manifest(file: &str) -> bool {
+    file.ends_with(".json")
+}
+
+/// Position and value of the `version = "..."` line in a Cargo.toml `[package]` section.
+fn cargo_package_version(content: &str) -> Option<(usize, String)> {
+    cargo_package_field(content, "version")
+}
+
+fn cargo_package_field(content: &str, key: &str) -> Option<(usize, String)> {
+    let mut in_package = false;
+    for (idx, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_package = trimmed == "[package]";
+            continue;
+        }
+        if !in_package {
+            continue;
+        }
+        if let Some(value) = toml_string_value(trimmed, key) {
+            return Some((idx, value));
+        }
+    }
+    None
+}
+
+/// Value of a `key = "value"` line.
+fn toml_string_value(line: &str, key: &str) -> Option<String> {
+    let value = line.strip_prefix(key)?.trim_start().strip_prefix('=')?.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .map(|v| v.to_string())
+}
+
+/// Rewrite the version of package `name` in a Cargo.lock.
+fn replace_lock_version(content: &str, name: &str, old: &str, new: &str) -> Option<String> {
+    let mut in_package = false;
+    let mut replaced = false;
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                in_package = false;
+            } else if let Some(package) = toml_string_value(trimmed, "name") {
+                in_package = package == name;
+            } else if in_package && !replaced && toml_string_value(trimmed, "version").as_deref() == Some(old) {
+                replaced = true;
+                return line.replacen(&format!("\"{}\"", old), &format!("\"{}\"", new), 1);
+            }
+            line.to_string()
+        })
+        .collect();
+
+    if !replaced {
+        return None;
+    }
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    Some(updated)
+}
+
+/// Lock files that may pin the crate of a Cargo.toml: its own directory's and
+/// the workspace root's.
+fn cargo_lock_candidates(manifest: &str) -> Vec<String> {
+    let mut candidates = vec![manifest.replace("Cargo.toml", "Cargo.lock")];
+    if manifest != "Cargo.toml" {
+        candidates.push("Cargo.lock".to_string());
+    }
+    candidates
+}
+
+fn read_manifest_version(file: &str, content: &str) -> Option<String> {
+    if is_json_manifest(file) {
+        let value: serde_json::Value = serde_json::from_str(content).ok()?;
+        value.get("version")?.as_str().map(|v| v.to_string())
+    } else {
+        cargo_package_version(content).map(|(_, version)| version)
+    }
+}
+
+/// Rewrite the version in place so the rest of the file keeps its formatting.
+fn replace_manifest_version(file: &str, content: &str, old: &str, new: &str) -> Option<String> {
+    if is_json_manifest(file) {
+        let mut search_from = 0;
+        while let Some(offset) = content[search_from..].find("\"version\"") {
+            let key_end = search_from + offset + "\"version\"".len();
+            let rest = content[key_end..].trim_start();
+            if let Some(value) = rest.strip_prefix(':').map(|r| r.trim_start()) {
+                let quoted = format!("\"{}\"", old);
+                if value.starts_with(&quoted) {
+                    let start = content.len() - value.len();
+                    return Some(format!(
+                        "{}\"{}\"{}",
+                        &content[..start],
+                        new,
+                        &content[start + quoted.len()..]
+                    ));
+                }
+            }
+            search_from = key_end;
+        }
+        None
+    } else {
+        let (line_idx, _) = cargo_package_version(content)?;
+        let lines: Vec<String> = content
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| {
+                if idx == line_idx {
+                    line.replacen(&format!("\"{}\"", old), &format!("\"{}\"", new), 1)
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect();
+        let mut updated = lines.join("\n");
+        if content.ends_with('\n') {
+            updated.push('\n');
+        }
+        Some(updated)
+    }
+}
+
+/// Bump a `MAJOR.MINOR.PATCH` version. Pre-release and build suffixes are dropped.
+fn bump_version(version: &str, bump: VersionBump) -> Result<String, GitError> {
+    let core = version.split(['-', '+']).next().unwrap_or("");
+    let parts: Vec<u64> = core
+        .split('.')
+        .map(|part| part.parse::<u64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| GitError::Git2(format!("'{}' is not a semantic version", version)))?;
+    let [major, minor, patch] = parts[..] else {
+        return Err(GitError::Git2(format!("'{}' is not a semantic version", version)));
+    };
+
+    Ok(match bump {
+        VersionBump::Major => format!("{}.0.0", major + 1),
+        VersionBump::Minor => format!("{}.{}.0", major, minor + 1),
+        VersionBump::Patch => format!("{}.{}.{}", major, minor, patch + 1),
+    })
+}
+
+/// Bump the project version, commit the manifests, tag the commit and
+/// optionally ship it. The first manifest found sets the current version;
+/// every manifest on that version is updated, along with the crate's entry in
+/// Cargo.lock.
+pub fn release(repo_path: &str, options: &ReleaseOptions) -> Result<ReleaseResult, GitError> {
+    let repo = open_repo(repo_path)?;
+    let workdir = repo_workdir(&repo)?.to_path_buf();
+
+    let mut status_opts = StatusOptions::new();
+    status_opts.include_untracked(false);
+    if !repo.statuses(Some(&mut status_opts))?.is_empty() {
+        return Err(GitError::Git2(
+            "Save or stash your changes before creating a release".to_string(),
+        ));
+    }
+
+    let manifests: Vec<(&str, String)> = VERSION_FILES
+        .iter()
+        .filter_map(|file| {
+            let content = std::fs::read_to_string(workdir.join(file)).ok()?;
+            Some((*file, content))
+        })
+        .collect();
+
+    let previous_version = manifests
+        .iter()
+        .find_map(|(file, content)| read_manifest_version(file, content))
+        .ok_or_else(|| {
+            GitError::Git2("No version found in package.json, Cargo.toml or tauri.conf.json".to_string())
+        })?;
+    let version = bump_version(&previous_version, options.bump)?;
+    let tag_name = format!("v{}", version);
+
+    if repo.find_reference(&format!("refs/tags/{}", tag_name)).is_ok() {
+        return Err(GitError::Git2(format!("Tag '{}' already exists", tag_name)));
+    }
+
+    let mut updates = Vec::new();
+    for (file, content) in &manifests {
+        if read_manifest_version(file, content).as_deref() != Some(previous_version.as_str()) {
+            continue;
+        }
+        let Some(updated) = replace_manifest_version(file, content, &previous_version, &version) else {
+            continue;
+        };
+        updates.push((file.to_string(), updated));
+
+        // Keep the crate's Cargo.lock entry in step so the release builds with --locked
+        if is_json_manifest(file) {
+            continue;
+        }
+        let Some((_, name)) = cargo_package_field(content, "name") else {
+            continue;
+        };
+        for lock in cargo_lock_candidates(file) {
+            if updates.iter().any(|(path, _)| *path == lock) {
+                continue;
+            }
+            let Ok(lock_content) = std::fs::read_to_string(workdir.join(&lock)) else {
+                continue;
+            };
+            if let Some(updated) = replace_lock_version(&lock_content, &name, &previous_version, &version) {
+                updates.push((lock, updated));
+                break;
+            }
+        }
+    }
+    if updates.is_empty() {
+        return Err(GitError::Git2(format!(
+            "Could not update version {} in any manifest",
+            previous_version
+        )));
+    }
+
+    let mut index = repo.index()?;
+    let mut files = Vec::new();
+    for (file, updated) in updates {
+        std::fs::write(workdir.join(&file), updated)?;
+        index.add_path(Path::new(&file))?;
+        files.push(file);
+    }
+    index.write()?;
+
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parent = repo.head()?.peel_to_commit()?;
+    let sig = repo.signature().unwrap_or_else(|_| {
+        Signature::now("ViboGit User", "user@vibogit.app").unwrap()
+    });
+    let message = format!("Release {}", version);
+    let oid = write_commit(&repo, &sig, &sig, &message, &tree, &[&parent])?;
+    advance_head(&repo, oid, "commit")?;
+
+    let commit = repo.find_object(oid, None)?;
+    repo.tag(&tag_name, &commit, &sig, &message, false)?;
+
+    let shipped = if options.ship {
+        let result = ship(repo_path)?;
+        push_tags(repo_path, Some(&result.remote), Some(&tag_name))?;
+        Some(result)
+    } else {
+        None
+    };
+
+    Ok(ReleaseResult {
+        previous_version,
+        version,
+        tag: tag_name,
+        sha: oid.to_string(),
+        files,
+        shipped,
+    })
+}
+
+// Worktrees
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Worktree {
+    pub path: String,
+    /// Checked out branch; `None` when detached.
+    pub branch: Option<String>,
+    pub head_sha: Option<String>,
+    /// The repository's original working tree, which cannot be removed.
+    pub is_main: bool,
+    pub is_locked: bool,
+    pub lock_reason: Option<String>,
+    /// Its directory is gone, so `prune_worktrees` will forget it.
+    pub is_prunable: bool,
+}
+
+fn run_worktree_command(repo_path: &str, args: &[&str]) -> Result<String, GitError> {
+    let output = std::process::Command::new("git")
+        .arg("worktree")
+        .args(args)
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| GitError::Io(format!("Failed to run git worktree: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        eprintln!("git worktree {} failed: {}", args.join(" "), stderr);
+        return Err(GitError::Git2(stderr.trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// All worktrees of the repository, the main one first.
+pub fn list_worktrees(repo_path: &str) -> Result<Vec<Worktree>, GitError> {
+    open_repo(repo_path)?;
+    let output = run_worktree_command(repo_path, &["list", "--porcelain"])?;
+
+    let mut worktrees: Vec<Worktree> = Vec::new();
+    for line in output.lines() {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        if key == "worktree" {
+            worktrees.push(Worktree {
+                path: value.to_string(),
+                is_main: worktrees.is_empty(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(worktree) = worktrees.last_mut() else {
+            continue;
+        };
+        match key {
+            "HEAD" => worktree.head_sha = Some(value.to_string()),
+            "branch" => {
+                worktree.branch = Some(value.strip_prefix("refs/heads/").unwrap_or(value).to_string())
+            }
+            "locked" => {
+                worktree.is_locked = true;
+                worktree.lock_reason = Some(value.to_string()).filter(|reason| !reason.is_empty());
+            }
+            "prunable" => worktree.is_prunable = true,
+            _ => {}
+        }
+    }
+
+    Ok(worktrees)
+}
+
+/// Check out a new branch `branch`, started from `base` (HEAD by default),
+/// in a new worktree at `path`.
+pub fn add_worktree(
+    repo_path: &str,
+    path: &str,
+    branch: &str,
+    base: Option<&str>,
+) -> Result<Worktree, GitError> {
+    let repo = open_repo(repo_path)?;
+    let base = base.unwrap_or("HEAD");
+    let base_sha = repo.revparse_single(base)?.peel_to_commit()?.id().to_string();
+    if repo.find_branch(branch, git2::BranchType::Local).is_ok() {
+        return Err(GitError::Git2(format!("Branch '{}' already exists", branch)));
+    }
+
+    run_worktree_command(repo_path, &["add", "-b", branch, path, &base_sha])?;
+
+    // git resolves relative paths against the repository, not our cwd
+    let added = std::fs::canonicalize(Path::new(repo_path).join(path))?;
+    list_worktrees(repo_path)?
+        .into_iter()
+        .find(|worktree| std::fs::canonicalize(&worktree.path).is_ok_and(|p| p == added))
+        .ok_or_else(|| GitError::Git2(format!("Worktree was not created at {}", path)))
+}
+
+/// Protect a worktree from being pruned or removed, e.g. while an agent is
+/// working in it.
+pub fn lock_worktree(repo_path: &str, path: &str, reason: Option<&str>) -> Result<(), GitError> {
+    let mut args = vec!["lock"];
+    if let Some(reason) = reason {
+        args.extend(["--reason", reason]);
+    }
+    args.push(path);
+    run_worktree_command(repo_path, &args)?;
+    Ok(())
+}
+
+pub fn unlock_worktree(repo_path: &str, path: &str) -> Result<(), GitError> {
+    run_worktree_command(repo_path, &["unlock", path])?;
+    Ok(())
+}
+
+/// Forget worktrees whose directories were deleted. Returns their paths.
+pub fn prune_worktrees(repo_path: &str) -> Result<Vec<String>, GitError> {
+    let prunable: Vec<String> = list_worktrees(repo_path)?
+        .into_iter()
+        .filter(|worktree| worktree.is_prunable && !worktree.is_locked)
+        .map(|worktree| worktree.path)
+        .collect();
+    run_worktree_command(repo_path, &["prune"])?;
+    Ok(prunable)
+}
+
+/// Delete a linked worktree. Without `force`, git refuses when it has
+/// uncommitted changes. Its branch is kept.
+pub fn remove_worktree(repo_path: &str, path: &str, force: bool) -> Result<(), GitError> {
+    let mut args = vec!["remove"];
+    if force {
+        args.push("--force");
+    }
+    args.push(path);
+    run_worktree_command(repo_path, &args)?;
+    Ok(())
+}
+
+/// The main working tree when `repo_path` is a linked worktree.
+pub fn main_worktree_path(repo_path: &str) -> Option<String> {
+    let repo = open_repo(repo_path).ok()?;
+    if !repo.is_worktree() {
+        return None;
+    }
+    list_worktrees(repo_path)
+        .ok()?
+        .into_iter()
+        .find(|worktree| worktree.is_main)
+        .map(|worktree| worktree.path)
+}
+
+// Submodules
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmoduleStatus {
+    pub name: String,
+    pub path: String,
+    pub url: Option<String>,
+    /// Commit recorded in the superproject's HEAD.
+    pub recorded_sha: Option<String>,
+    /// Commit checked out in the submodule; `None` until it is cloned.
+    pub checked_out_sha: Option<String>,
+    pub is_initialized: bool,
+    /// Uncommitted or untracked changes inside the submodule.
+    pub is_dirty: bool,
+}
+
+pub fn list_submodules(repo_path: &str) -> Result<Vec<SubmoduleStatus>, GitError> {
+    let repo = open_repo(repo_path)?;
+    submodule_statuses(&repo)
+}
+
+fn submodule_statuses(repo: &Repository) -> Result<Vec<SubmoduleStatus>, GitError> {
+    let config = repo.config()?;
+    let mut submodules = Vec::new();
+    for submodule in repo.submodules()? {
+        let name = submodule.name().unwrap_or("").to_string();
+        let status = repo.submodule_status(&name, git2::SubmoduleIgnore::None)?;
+
+        submodules.push(SubmoduleStatus {
+            path: submodule.path().to_string_lossy().to_string(),
+            url: submodule.url().map(str::to_string),
+            recorded_sha: submodule.head_id().map(|oid| oid.to_string()),
+            checked_out_sha: submodule.workdir_id().map(|oid| oid.to_string()),
+            // `git submodule init` copies the URL into the local config
+            is_initialized: config.get_string(&format!("submodule.{}.url", name)).is_ok(),
+            is_dirty: status.intersects(
+                git2::SubmoduleStatus::WD_INDEX_MODIFIED
+                    | git2::SubmoduleStatus::WD_WD_MODIFIED
+                    | git2::SubmoduleStatus::WD_UNTRACKED,
+            ),
+            name,
+        });
+    }
+    Ok(submodules)
+}
+
+fn run_submodule_command(repo_path: &str, args: &[&str]) -> Result<Vec<SubmoduleStatus>, GitError> {
+    let output = std::process::Command::new("git")
+        .arg("submodule")
+        .args(args)
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| GitError::Io(format!("Failed to run git submodule: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        eprintln!("git submodule {} failed: {}", args.join(" "), stderr);
+        // Updates clone and fetch, so they fail the same ways a pull does
+        return Err(classify_pull_error(&stderr));
+    }
+    list_submodules(repo_path)
+}
+
+/// Register submodules from `.gitmodules` in the local config.
+pub fn init_submodules(repo_path: &str) -> Result<Vec<SubmoduleStatus>, GitError> {
+    run_submodule_command(repo_path, &["init"])
+}
+
+/// Clone missing submodules and check out their recorded commits.
+pub fn update_submodules(repo_path: &str, recursive: bool) -> Result<Vec<SubmoduleStatus>, GitError> {
+    let mut args = vec!["update", "--init"];
+    if recursive {
+        args.push("--recursive");
+    }
+    run_submodule_command(repo_path, &args)
+}
+
+/// Copy URL changes from `.gitmodules` into the local config.
+pub fn sync_submodules(repo_path: &str) -> Result<Vec<SubmoduleStatus>, GitError> {
+    run_submodule_command(repo_path, &["sync", "--recursive"])
+}
+
+// Repository Health
+
+/// Lock files younger than this are assumed to belong to a running git process.
+const STALE_LOCK_AGE_SECS: u64 = 30;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RecoveryAction {
+    #[serde(rename_all = "camelCase")]
+    RemoveLockFile { path: String },
+    AbortOperation,
+    ContinueOperation,
+    /// Forget the in-progress operation but keep the index and working tree.
+    CleanupState,
+    ResetBisect,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthIssue {
+    pub message: String,
+    pub actions: Vec<RecoveryAction>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RepoHealth {
+    pub healthy: bool,
+    pub operation: RepoOperation,
+    pub issues: Vec<HealthIssue>,
+}
+
+fn find_lock_files(git_dir: &Path) -> Vec<std::path::PathBuf> {
+    let mut locks: Vec<std::path::PathBuf> = ["index.lock", "HEAD.lock", "config.lock", "packed-refs.lock"]
+        .iter()
+        .map(|name| git_dir.join(name))
+        .filter(|path| path.exists())
+        .collect();
+
+    let mut pending = vec![git_dir.join("refs")];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "lock") {
+                locks.push(path);
+            }
+        }
+    }
+
+    locks
+}
+
+pub fn check_repo_health(repo_path: &str) -> Result<RepoHealth, GitError> {
+    let repo = open_repo(repo_path)?;
+    let git_dir = repo.path();
+    let operation: RepoOperation = repo.state().into();
+    let mut issues = Vec::new();
+
+    for lock in find_lock_files(git_dir) {
+        let age_secs = std::fs::metadata(&lock)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|age| age.as_secs())
+            .unwrap_or(0);
+        let relative = lock.strip_prefix(git_dir).unwrap_or(&lock).to_string_lossy().to_string();
+
+        if age_secs >= STALE_LOCK_AGE_SECS {
+            issues.push(HealthIssue {
+                message: format!(
+                    "Stale lock file .git/{} ({}s old) is blocking git operations",
+                    relative, age_secs
+                ),
+                actions: vec![RecoveryAction::RemoveLockFile { path: relative }],
+            });
+        } else {
+            issues.push(HealthIssue {
+                message: format!("Lock file .git/{} is held by a running git process", relative),
+                actions: vec![],
+            });
+        }
+    }
+
+    if operation != RepoOperation::None {
+        let has_conflicts = repo.index()?.has_conflicts();
+        let label = match operation {
+            RepoOperation::Merge => "A merge",
+            RepoOperation::Rebase => "A rebase",
+            RepoOperation::CherryPick => "A cherry-pick",
+            RepoOperation::Revert => "A revert",
+            RepoOperation::Bisect => "A bisect",
+            RepoOperation::ApplyMailbox => "Applying patches",
+            RepoOperation::None => "An operation",
+        };
+
+        let mut message = format!("{} is in progress", label);
+        if let Some(progress) = read_rebase_progress(git_dir) {
+            message.push_str(&format!(" (step {} of {})", progress.current, progress.total));
+        }
+        if has_conflicts {
+            message.push_str(" with unresolved conflicts");
+        }
+
+        let actions = match operation {
+            RepoOperation::Bisect => vec![RecoveryAction::ResetBisect],
+            RepoOperation::Merge | RepoOperation::CherryPick | RepoOperation::Revert => {
+                let mut actions = vec![RecoveryAction::AbortOperation];
+                if !has_conflicts {
+                    actions.push(RecoveryAction::ContinueOperation);
+                    actions.push(RecoveryAction::CleanupState);
+                }
+                actions
+            }
+            _ => {
+                let mut actions = vec![RecoveryAction::AbortOperation];
+                if !has_conflicts {
+                    actions.push(RecoveryAction::ContinueOperation);
+                }
+                actions
+            }
+        };
+
+        issues.push(HealthIssue { message, actions });
+    }
+
+    Ok(RepoHealth {
+        healthy: issues.is_empty(),
+        operation,
+        issues,
+    })
+}
+
+pub fn recover_repo(repo_path: &str, action: &RecoveryAction) -> Result<RepoHealth, GitError> {
+    match action {
+        RecoveryAction::RemoveLockFile { path } => {
+            let repo = open_repo(repo_path)?;
+            let relative = Path::new(path);
+            let is_safe = relative.extension().is_some_and(|ext| ext == "lock")
+                && relative
+                    .components()
+                    .all(|component| matches!(component, std::path::Component::Normal(_)));
+            if !is_safe {
+                return Err(GitError::Io(format!("Refusing to remove non-lock file: {}", path)));
+            }
+            let lock = repo.path().join(relative);
+            if lock.exists() {
+                std::fs::remove_file(lock)?;
+            }
+        }
+        RecoveryAction::AbortOperation => {
+            abort_operation(repo_path)?;
+        }
+        RecoveryAction::ContinueOperation => {
+            continue_operation(repo_path)?;
+        }
+        RecoveryAction::CleanupState => {
+            open_repo(repo_path)?.cleanup_state()?;
+        }
+        RecoveryAction::ResetBisect => {
+            let output = std::process::Command::new("git")
+                .args(["bisect", "reset"])
+                .current_dir(repo_path)
+                .output()
+                .map_err(|e| GitError::Io(format!("Failed to run git bisect reset: {}", e)))?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(GitError::Git2(stderr.trim().to_string()));
+            }
+        }
+    }
+
+    check_repo_health(repo_path)
+}
+
+// Ref Snapshots
+
+/// Local refs and HEAD at a point in time. Remote-tracking refs are left out
+/// because they mirror the remote and cannot be restored locally.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RefSnapshot {
+    /// Symbolic target such as `refs/heads/main`, or the sha when detached.
+    pub head: String,
+    pub head_sha: Option<String>,
+    pub refs: std::collections::BTreeMap<String, String>,
+    pub stash_count: usize,
+}
+
+const STASH_REF: &str = "refs/stash";
+
+pub fn capture_ref_snapshot(repo_path: &str) -> Result<RefSnapshot, GitError> {
+    let repo = open_repo(repo_path)?;
+
+    let head_ref = repo.find_reference("HEAD")?;
+    let head = match head_ref.symbolic_target() {
+        Some(target) => target.to_string(),
+        None => head_ref.target().map(|oid| oid.to_string()).unwrap_or_default(),
+    };
+    let head_sha = repo
+        .head()
+        .ok()
+        .and_then(|reference| reference.target())
+        .map(|oid| oid.to_string());
+
+    let mut refs = std::collections::BTreeMap::new();
+    for reference in repo.references()?.flatten() {
+        let (Some(name), Some(target)) = (reference.name(), reference.target()) else {
+            continue;
+        };
+        if name.starts_with("refs/heads/") || name.starts_with("refs/tags/") || name == STASH_REF {
+            refs.insert(name.to_string(), target.to_string());
+        }
+    }
+
+    let stash_count = repo.reflog(STASH_REF).map(|reflog| reflog.len()).unwrap_or(0);
+
+    Ok(RefSnapshot {
+        head,
+        head_sha,
+        refs,
+        stash_count,
+    })
+}
+
+/// Fail if rolling back from `after` to `before` would drop commits that a
+/// branch has already shipped to its upstream.
+pub fn ensure_snapshot_not_pushed(
+    repo_path: &str,
+    before: &RefSnapshot,
+    after: &RefSnapshot,
+) -> Result<(), GitError> {
+    let repo = open_repo(repo_path)?;
+
+    for (name, new) in &after.refs {
+        let Some(branch) = name.strip_prefix("refs/heads/") else {
+            continue;
+        };
+        let old = before.refs.get(name);
+        if old == Some(new) {
+            continue;
+        }
+
+        let new_oid = git2::Oid::from_str(new)?;
+        // Moving a branch forward again never drops shipped commits
+        if let Some(old) = old {
+            let old_oid = git2::Oid::from_str(old)?;
+            if repo.graph_descendant_of(old_oid, new_oid)? {
+                continue;
+            }
+        }
+
+        if is_on_upstream(&repo, branch, new_oid)? {
+            return Err(GitError::AlreadyPushed(format!(
+                "Cannot undo because {} has already been shipped",
+                branch
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Move the repository from the `after` snapshot back to `before`.
+///
+/// Refuses when the repository no longer matches `after`, so an undo never
+/// clobbers work done outside the journal. With `keep_worktree` only refs move
+/// (like a soft reset); otherwise the working tree is checked out safely first
+/// and locally modified files block the undo.
+pub fn restore_ref_snapshot(
+    repo_path: &str,
+    before: &RefSnapshot,
+    after: &RefSnapshot,
+    keep_worktree: bool,
+) -> Result<(), GitError> {
+    let current = capture_ref_snapshot(repo_path)?;
+    if current != *after {
+        return Err(GitError::Git2(
+            "The repository has changed since this operation; undo the newer changes first".to_string(),
+        ));
+    }
+
+    let repo = open_repo(repo_path)?;
+
+    if !keep_worktree && before.head_sha != after.head_sha {
+        if let Some(sha) = &before.head_sha {
+            let tree = repo.find_commit(git2::Oid::from_str(sha)?)?.tree()?;
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.safe();
+            repo.checkout_tree(tree.as_object(), Some(&mut checkout))?;
+        }
+    }
+
+    let names: std::collections::BTreeSet<&String> = before.refs.keys().chain(after.refs.keys()).collect();
+    for name in names {
+        if name == STASH_REF {
+            continue;
+        }
+        match (before.refs.get(name), after.refs.get(name)) {
+            (Some(old), new) if Some(old) != new => {
+                repo.reference(name, git2::Oid::from_str(old)?, true, "vibogit: undo")?;
+            }
+            (None, Some(_)) => {
+                repo.find_reference(name)?.delete()?;
+            }
+            _ => {}
+        }
+    }
+
+    if before.head != after.head {
+        if before.head.starts_with("refs/") {
+            repo.set_head(&before.head)?;
+        } else {
+            repo.set_head_detached(git2::Oid::from_str(&before.head)?)?;
+        }
+    }
+
+    // Restore the stash stack through git so its reflog stays intact
+    if before.stash_count != after.stash_count {
+        let args: Vec<&str> = if after.stash_count > before.stash_count {
+            vec!["stash", "pop", "--index"]
+        } else if let Some(sha) = before.refs.get(STASH_REF) {
+            vec!["stash", "store", "-m", "Restored by undo", sha]
+        } else {
+            vec![]
+        };
+
+        if !args.is_empty() {
+            let output = std::process::Command::new("git")
+                .args(&args)
+                .current_dir(repo_path)
+                .output()
+                .map_err(|e| GitError::Io(format!("Failed to run git {}: {}", args.join(" "), e)))?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(GitError::Git2(stderr.trim().to_string()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn init_repo(path: &str) -> Result<(), GitError> {
+    Repository::init(path)?;
+    Ok(())
+}
+
+/// Classify a git push stderr message into the appropriate GitError variant.
+/// This is extracted for testability.
+pub fn classify_push_error(stderr: &str, rebased: bool) -> GitError {
+    let stderr_lower = stderr.to_lowercase();
+
+    // Auth failures
+    if stderr_lower.contains("authentication")
+        || stderr_lower.contains("could not read username")
+        || stderr_lower.contains("permission denied")
+    {
+        return GitError::AuthFailed(stderr.trim().to_string());
+    }
+
+    // Non-fast-forward or rejected
+    if stderr_lower.contains("rejected")
+        || stderr_lower.contains("non-fast-forward")
+        || stderr_lower.contains("failed to push")
+    {
+        return GitError::PushRejected(stderr.trim().to_string());
+    }
+
+    // If rebase succeeded but push failed, give a descriptive error
+    if rebased {
+        return GitError::PushRejected(format!(
+            "Rebase succeeded but push failed: {}",
+            stderr.trim()
+        ));
+    }
+
+    GitError::PushRejected(stderr.trim().to_string())
+}
+
+/// Classify a failed git pull (without conflicts) into the appropriate GitError variant.
+pub fn classify_pull_error(stderr: &str) -> GitError {
+    let stderr_lower = stderr.to_lowercase();
+
+    if stderr_lower.contains("authentication")
+        || stderr_lower.contains("could not read username")
+        || stderr_lower.contains("permission denied")
+    {
+        return GitError::AuthFailed(stderr.trim().to_string());
+    }
+
+    if stderr_lower.contains("not possible to fast-forward")
+        || stderr_lower.contains("diverging branches")
+        || stderr_lower.contains("have diverged")
+    {
+        return GitError::Diverged(stderr.trim().to_string());
+    }
+
+    if stderr_lower.contains("could not resolve host")
+        || stderr_lower.contains("unable to access")
+        || stderr_lower.contains("connection refused")
+        || stderr_lower.contains("connection timed out")
+        || stderr_lower.contains("network is unreachable")
+        || stderr_lower.contains("could not read from remote repository")
+    {
+        return GitError::Network(stderr.trim().to_string());
+    }
+
+    GitError::Git2(stderr.trim().to_string())
+}
+
+/// Classify a git pull --rebase stderr message into the appropriate GitError variant.
+/// Returns None if it looks like a conflict (caller should abort rebase first).
+pub fn classify_rebase_error(stderr: &str) -> GitError {
+    let stderr_lower = stderr.to_lowercase();
+
+    if stderr_lower.contains("conflict")
+        || stderr_lower.contains("could not apply")
+        || stderr_lower.contains("merge conflict")
+    {
+        return GitError::RebaseConflict(
+            "Rebase conflict detected. Your local changes conflict with remote changes. Please resolve conflicts manually.".to_string()
+        );
+    }
+
+    if stderr_lower.contains("authentication")
+        || stderr_lower.contains("could not read username")
+        || stderr_lower.contains("permission denied")
+    {
+        return GitError::AuthFailed(stderr.trim().to_string());
+    }
+
+    GitError::PushRejected(format!("Pull --rebase failed: {}", stderr.trim()))
+}
+
+/// Errors from a local `git rebase` that stopped without conflicts, such as a
+/// dirty working tree or a rejected todo list. Only conflicts and auth
+/// failures keep their classified variants.
+fn local_rebase_error(output: &str) -> GitError {
+    match classify_rebase_error(output) {
+        GitError::PushRejected(_) => GitError::Git2(format!("Rebase failed: {}", output.trim())),
+        err => err,
+    }
+}
+
+#[cfg(test)]
+mod ship_tests {
+    use super::*;
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        process::Command,
+    };
+
+    fn run_git<I, S>(cwd: &Path, args: I) -> std::process::Output
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        let output = Command::new("git").args(args).current_dir(cwd).output().unwrap();
+        assert!(
+            output.status.success(),
+            "git command failed in {}: stdout={} stderr={}",
+            cwd.display(),
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output
+    }
+
+    fn run_git_allow_failure<I, S>(cwd: &Path, args: I) -> std::process::Output
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        Command::new("git").args(args).current_dir(cwd).output().unwrap()
+    }
+
+    fn write_file(path: &Path, contents: &str) {
+        fs::write(path, contents).unwrap();
+    }
+
+    fn commit_file(repo_path: &Path, file_name: &str, contents: &str, message: &str) {
+        write_file(&repo_path.join(file_name), contents);
+        run_git(repo_path, ["add", file_name]);
+        run_git(repo_path, ["commit", "-m", message]);
+    }
+
+    fn clone_repo_on_branch(remote_path: &Path, destination: &Path, branch: &str) {
+        let output = Command::new("git")
+            .args([
+                "clone",
+                "--branch",
+                branch,
+                remote_path.to_str().unwrap(),
+                destination.to_str().unwrap(),
+            ])
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git clone --branch failed: stdout={} stderr={}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    fn get_head(repo_path: &Path) -> String {
+        let output = run_git(repo_path, ["rev-parse", "HEAD"]);
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    fn has_rebase_state(repo_path: &Path) -> bool {
+        let git_dir_output = run_git(repo_path, ["rev-parse", "--git-dir"]);
+        let git_dir = String::from_utf8(git_dir_output.stdout).unwrap();
+        let git_dir = repo_path.join(git_dir.trim());
+        git_dir.join("rebase-merge").exists() || git_dir.join("rebase-apply").exists()
+    }
+
+    fn setup_repo_with_remote() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let temp = tempfile::tempdir().unwrap();
+        let remote_path = temp.path().join("remote.git");
+        let repo_path = temp.path().join("local");
+
+        let init_remote = Command::new("git")
+            .args(["init", "--bare", remote_path.to_str().unwrap()])
+            .output()
+            .unwrap();
+        assert!(
+            init_remote.status.success(),
+            "git init --bare failed: stdout={} stderr={}",
+            String::from_utf8_lossy(&init_remote.stdout),
+            String::from_utf8_lossy(&init_remote.stderr)
+        );
+
+        fs::create_dir_all(&repo_path).unwrap();
+        run_git(&repo_path, ["init", "-b", "main"]);
+        run_git(&repo_path, ["config", "user.name", "Test User"]);
+        run_git(&repo_path, ["config", "user.email", "test@example.com"]);
+        run_git(
+            &repo_path,
+            ["remote", "add", "origin", remote_path.to_str().unwrap()],
+        );
+
+        (temp, repo_path, remote_path)
+    }
+
+    #[test]
+    fn classify_push_error_auth_failed() {
+        let err = classify_push_error("fatal: Authentication failed for 'https://github.com/...'", false);
+        match err {
+            GitError::AuthFailed(msg) => {
+                assert!(msg.contains("Authentication failed"));
+            }
+            other => panic!("Expected AuthFailed, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_push_error_permission_denied() {
+        let err = classify_push_error("ERROR: Permission denied (publickey).\nfatal: Could not read from remote.", false);
+        match err {
+            GitError::AuthFailed(msg) => {
+                assert!(msg.contains("Permission denied"));
+            }
+            other => panic!("Expected AuthFailed, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_push_error_rejected_non_fast_forward() {
+        let err = classify_push_error("error: failed to push some refs to 'origin'\nhint: Updates were rejected because the remote contains work", false);
+        match err {
+            GitError::PushRejected(msg) => {
+                assert!(msg.contains("failed to push"));
+            }
+            other => panic!("Expected PushRejected, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_push_error_non_fast_forward() {
+        let err = classify_push_error("To github.com:user/repo.git\n ! [rejected]        main -> main (non-fast-forward)", false);
+        match err {
+            GitError::PushRejected(msg) => {
+                assert!(msg.contains("non-fast-forward"));
+            }
+            other => panic!("Expected PushRejected, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_push_error_after_rebase() {
+        let err = classify_push_error("fatal: unable to access 'https://github.com/...'", true);
+        match err {
+            GitError::PushRejected(msg) => {
+                assert!(msg.contains("Rebase succeeded but push failed"));
+            }
+            other => panic!("Expected PushRejected with rebase context, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_push_error_generic() {
+        let err = classify_push_error("fatal: some unknown error", false);
+        match err {
+            GitError::PushRejected(msg) => {
+                assert!(msg.contains("some unknown error"));
+                // Verify it's NOT AuthFailed
+                assert!(!msg.contains("Authentication"));
+            }
+            other => panic!("Expected PushRejected, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_rebase_error_conflict() {
+        let err = classify_rebase_error("CONFLICT (content): Merge conflict in file.txt\nerror: could not apply abc1234... Fix");
+        match err {
+            GitError::RebaseConflict(msg) => {
+                assert!(msg.contains("conflict"));
+            }
+            other => panic!("Expected RebaseConflict, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_rebase_error_could_not_apply() {
+        let err = classify_rebase_error("error: could not apply 123abc4... some commit message");
+        match err {
+            GitError::RebaseConflict(msg) => {
+                assert!(msg.contains("conflict"));
+            }
+            other => panic!("Expected RebaseConflict, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_rebase_error_auth() {
+        let err = classify_rebase_error("fatal: Authentication failed for 'https://github.com/...'");
+        match err {
+            GitError::AuthFailed(msg) => {
+                assert!(msg.contains("Authentication"));
+            }
+            other => panic!("Expected AuthFailed, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_rebase_error_generic() {
+        let err = classify_rebase_error("fatal: unable to access remote");
+        match err {
+            GitError::PushRejected(msg) => {
+                assert!(msg.contains("Pull --rebase failed"));
+            }
+            other => panic!("Expected PushRejected, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ship_result_serializes_with_rebased_field() {
+        let result = ShipResult {
+            pushed: true,
+            commits_pushed: 3,
+            remote: "origin".to_string(),
+            branch: "main".to_string(),
+            rebased: true,
+        };
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(json.contains("\"rebased\":true"));
+        assert!(json.contains("\"commitsPushed\":3"));
+    }
+
+    #[test]
+    fn ship_result_no_rebase() {
+        let result = ShipResult {
+            pushed: true,
+            commits_pushed: 1,
+            remote: "origin".to_string(),
+            branch: "main".to_string(),
+            rebased: false,
+        };
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(json.contains("\"rebased\":false"));
+    }
+
+    #[test]
+    fn git_error_rebase_conflict_display() {
+        let err = GitError::RebaseConflict("test conflict".to_string());
+        assert_eq!(format!("{}", err), "Rebase conflict: test conflict");
+    }
+
+    #[test]
+    fn git_error_push_rejected_display() {
+        let err = GitError::PushRejected("test rejection".to_string());
+        assert_eq!(format!("{}", err), "Push rejected: test rejection");
+    }
+
+    #[test]
+    fn git_error_serializes_correctly() {
+        let rebase_err = GitError::RebaseConflict("conflict details".to_string());
+        let json = serde_json::to_string(&rebase_err).unwrap();
+        assert!(json.contains("RebaseConflict"));
+
+        let push_err = GitError::PushRejected("push details".to_string());
+        let json = serde_json::to_string(&push_err).unwrap();
+        assert!(json.contains("PushRejected"));
+    }
+
+    #[test]
+    fn ship_no_remote_returns_error() {
+        // Create a temp repo without any remote
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = Repository::init(tmp.path()).unwrap();
+
+        // Create an initial commit so HEAD exists
+        let sig = Signature::now("Test", "test@test.com").unwrap();
+        let tree_id = {
+            let mut index = repo.index().unwrap();
+            index.write_tree().unwrap()
+        };
+        let tree = repo.find_tree(tree_id).unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Initial", &tree, &[]).unwrap();
+
+        let result = ship(tmp.path().to_str().unwrap());
+        match result {
+            Err(GitError::NoRemote) => {} // expected
+            other => panic!("Expected NoRemote, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ship_simple_push_updates_remote_and_clears_ahead() {
+        let (_temp, repo_path, remote_path) = setup_repo_with_remote();
+        commit_file(&repo_path, "file.txt", "hello\n", "initial commit");
+
+        let result = ship(repo_path.to_str().unwrap()).unwrap();
+        assert!(result.pushed);
+        assert_eq!(result.branch, "main");
+        assert_eq!(result.remote, "origin");
+        assert_eq!(result.commits_pushed, 1);
+        assert!(!result.rebased);
+
+        let status = get_status(repo_path.to_str().unwrap()).unwrap();
+        assert_eq!(status.ahead, 0);
+        assert_eq!(status.behind, 0);
+        assert!(status.has_remote);
+
+        let remote_head_output = run_git(&remote_path, ["rev-parse", "refs/heads/main"]);
+        let remote_head = String::from_utf8(remote_head_output.stdout).unwrap().trim().to_string();
+        assert_eq!(get_head(&repo_path), remote_head);
+    }
+
+    #[test]
+    fn ship_rebases_then_pushes_when_remote_is_ahead() {
+        let (_temp, repo_path, remote_path) = setup_repo_with_remote();
+        commit_file(&repo_path, "shared.txt", "base\n", "base commit");
+        run_git(&repo_path, ["push", "-u", "origin", "main"]);
+
+        let other_dir = tempfile::tempdir().unwrap();
+        let other_path = other_dir.path().join("other");
+        clone_repo_on_branch(&remote_path, &other_path, "main");
+        run_git(&other_path, ["config", "user.name", "Remote User"]);
+        run_git(&other_path, ["config", "user.email", "remote@example.com"]);
+        commit_file(&other_path, "shared.txt", "base\nremote change\n", "remote commit");
+        run_git(&other_path, ["push", "origin", "main"]);
+
+        run_git(&repo_path, ["fetch", "origin"]);
+
+        commit_file(&repo_path, "local.txt", "local change\n", "local commit");
+
+        let result = ship(repo_path.to_str().unwrap()).unwrap();
+        assert!(result.pushed);
+        assert!(result.rebased);
+        assert_eq!(result.commits_pushed, 1);
+
+        let status = get_status(repo_path.to_str().unwrap()).unwrap();
+        assert_eq!(status.ahead, 0);
+        assert_eq!(status.behind, 0);
+
+        let log_output = run_git(&repo_path, ["log", "--oneline", "--decorate=short", "-3"]);
+        let log = String::from_utf8(log_output.stdout).unwrap();
+        assert!(log.contains("local commit"));
+        assert!(log.contains("remote commit"));
+
+        let remote_head = String::from_utf8(run_git(&remote_path, ["rev-parse", "refs/heads/main"]).stdout)
+            .unwrap()
+            .trim()
+            .to_string();
+        assert_eq!(get_head(&repo_path), remote_head);
+        assert!(String::from_utf8(run_git(&remote_path, ["show", "refs/heads/main:local.txt"]).stdout)
+            .unwrap()
+            .contains("local change"));
+        assert!(String::from_utf8(run_git(&remote_path, ["show", "refs/heads/main:shared.txt"]).stdout)
+            .unwrap()
+            .contains("remote change"));
+    }
+
+    #[test]
+    fn ship_conflict_aborts_rebase_and_leaves_repo_clean() {
+        let (_temp, repo_path, remote_path) = setup_repo_with_remote();
+        commit_file(&repo_path, "shared.txt", "base\n", "base commit");
+        run_git(&repo_path, ["push", "-u", "origin", "main"]);
+
+        let before_ship_head = get_head(&repo_path);
+
+        let other_dir = tempfile::tempdir().unwrap();
+        let other_path = other_dir.path().join("other");
+        clone_repo_on_branch(&remote_path, &other_path, "main");
+        run_git(&other_path, ["config", "user.name", "Remote User"]);
+        run_git(&other_path, ["config", "user.email", "remote@example.com"]);
+        commit_file(&other_path, "shared.txt", "remote version\n", "remote conflict");
+        run_git(&other_path, ["push", "origin", "main"]);
+
+        run_git(&repo_path, ["fetch", "origin"]);
+
+        commit_file(&repo_path, "shared.txt", "local version\n", "local conflict");
+        let local_head_before_ship = get_head(&repo_path);
+
+        let result = ship(repo_path.to_str().unwrap());
+        match result {
+            Err(GitError::RebaseConflict(message)) => assert!(message.to_lowercase().contains("conflict")),
+            other => panic!("Expected RebaseConflict, got: {:?}", other),
+        }
+
+        assert_eq!(get_head(&repo_path), local_head_before_ship);
+        assert_ne!(local_head_before_ship, before_ship_head);
+        assert!(!has_rebase_state(&repo_path));
+
+        let status_output = run_git(&repo_path, ["status", "--short"]);
+        assert!(String::from_utf8(status_output.stdout).unwrap().trim().is_empty());
+        assert_eq!(fs::read_to_string(repo_path.join("shared.txt")).unwrap(), "local version\n");
+
+        let rebase_abort_check = run_git_allow_failure(&repo_path, ["rebase", "--abort"]);
+        assert!(!rebase_abort_check.status.success());
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use git2::Repository;
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        process::Command,
+    };
+
+    pub fn run_git<I, S>(cwd: &Path, args: I) -> String
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        let output = Command::new("git").args(args).current_dir(cwd).output().unwrap();
+        assert!(
+            output.status.success(),
+            "git command failed in {}: stdout={} stderr={}",
+            cwd.display(),
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    pub fn write_file(repo_path: &Path, file_name: &str, contents: &str) {
+        let path = repo_path.join(file_name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, contents).unwrap();
+    }
+
+    pub fn commit_file(repo_path: &Path, file_name: &str, contents: &str, message: &str) {
+        write_file(repo_path, file_name, contents);
+        run_git(repo_path, ["add", file_name]);
+        run_git(repo_path, ["commit", "-m", message]);
+    }
+
+    pub fn init_test_repo() -> (tempfile::TempDir, PathBuf) {
+        let temp = tempfile::tempdir().unwrap();
+        let repo_path = temp.path().join("repo");
+        fs::create_dir_all(&repo_path).unwrap();
+        Repository::init(&repo_path).unwrap();
+        run_git(&repo_path, ["symbolic-ref", "HEAD", "refs/heads/main"]);
+        run_git(&repo_path, ["config", "user.name", "Test User"]);
+        run_git(&repo_path, ["config", "user.email", "test@example.com"]);
+        run_git(&repo_path, ["config", "commit.gpgsign", "false"]);
+        (temp, repo_path)
+    }
+
+    pub fn path_str(path: &Path) -> &str {
+        path.to_str().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod partial_staging_tests {
+    use super::test_support::*;
+    use super::*;
+
+    const BASE: &str = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n";
+    const FIRST_HUNK_ONLY: &str = "1\nTWO\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n";
+
+    fn setup_two_hunks() -> (tempfile::TempDir, std::path::PathBuf) {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", BASE, "base");
+        write_file(
+            &repo_path,
+            "file.txt",
+            "1\nTWO\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\nFOURTEEN\n15\n",
+        );
+        (temp, repo_path)
+    }
+
+    #[test]
+    fn stage_single_hunk_leaves_other_hunk_unstaged() {
+        let (_temp, repo_path) = setup_two_hunks();
+        let diff = get_unstaged_file_diff(path_str(&repo_path), "file.txt").unwrap();
+        assert_eq!(diff.hunks.len(), 2);
+
+        stage_hunks(
+            path_str(&repo_path),
+            &diff,
+            &[HunkSelection { hunk_index: 0, line_indices: None }],
+        )
+        .unwrap();
+
+        let staged = run_git(&repo_path, ["show", ":file.txt"]);
+        assert_eq!(staged, FIRST_HUNK_ONLY);
+        let unstaged = run_git(&repo_path, ["diff", "--name-only"]);
+        assert_eq!(unstaged.trim(), "file.txt");
+    }
+
+    #[test]
+    fn stage_individual_lines_within_hunk() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "a\nb\nc\n", "base");
+        write_file(&repo_path, "file.txt", "a\ndebug1\nb\nkeep\nc\n");
+
+        let diff = get_unstaged_file_diff(path_str(&repo_path), "file.txt").unwrap();
+        assert_eq!(diff.hunks.len(), 1);
+        let keep_index = diff.hunks[0]
+            .lines
+            .iter()
+            .position(|line| line.content == "keep\n")
+            .unwrap();
+
+        stage_hunks(
+            path_str(&repo_path),
+            &diff,
+            &[HunkSelection { hunk_index: 0, line_indices: Some(vec![keep_index]) }],
+        )
+        .unwrap();
+
+        assert_eq!(run_git(&repo_path, ["show", ":file.txt"]), "a\nb\nkeep\nc\n");
+    }
+
+    #[test]
+    fn unstage_single_hunk_keeps_other_hunk_staged() {
+        let (_temp, repo_path) = setup_two_hunks();
+        run_git(&repo_path, ["add", "file.txt"]);
+
+        let diff = get_file_diff(path_str(&repo_path), "file.txt", true).unwrap();
+        unstage_hunks(
+            path_str(&repo_path),
+            &diff,
+            &[HunkSelection { hunk_index: 1, line_indices: None }],
+        )
+        .unwrap();
+
+        let staged = run_git(&repo_path, ["show", ":file.txt"]);
+        assert_eq!(staged, FIRST_HUNK_ONLY);
+    }
+
+    #[test]
+    fn fully_staged_file_diffs_against_head_but_not_index() {
+        let (_temp, repo_path) = setup_two_hunks();
+        run_git(&repo_path, ["add", "file.txt"]);
+
+        let diff = get_file_diff(path_str(&repo_path), "file.txt", false).unwrap();
+        assert_eq!(diff.hunks.len(), 2);
+        let unstaged = get_unstaged_file_diff(path_str(&repo_path), "file.txt").unwrap();
+        assert!(unstaged.hunks.is_empty());
+    }
+
+    #[test]
+    fn empty_selection_is_rejected() {
+        let (_temp, repo_path) = setup_two_hunks();
+        let diff = get_unstaged_file_diff(path_str(&repo_path), "file.txt").unwrap();
+        assert!(stage_hunks(path_str(&repo_path), &diff, &[]).is_err());
+    }
+
+    #[test]
+    fn out_of_range_selection_is_rejected() {
+        let (_temp, repo_path) = setup_two_hunks();
+        let diff = get_unstaged_file_diff(path_str(&repo_path), "file.txt").unwrap();
+
+        let missing_hunk = [
+            HunkSelection { hunk_index: 0, line_indices: None },
+            HunkSelection { hunk_index: 5, line_indices: None },
+        ];
+        assert!(stage_hunks(path_str(&repo_path), &diff, &missing_hunk).is_err());
+        let missing_line = [HunkSelection { hunk_index: 0, line_indices: Some(vec![99]) }];
+        assert!(stage_hunks(path_str(&repo_path), &diff, &missing_line).is_err());
+        assert_eq!(run_git(&repo_path, ["diff", "--cached", "--name-only"]), "");
+    }
+
+    #[test]
+    fn stage_hunk_of_line_already_staged_and_edited_again() {
+        let (_temp, repo_path) = setup_two_hunks();
+        run_git(&repo_path, ["add", "file.txt"]);
+        write_file(
+            &repo_path,
+            "file.txt",
+            "1\nTWO!\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\nFOURTEEN\n15\n",
+        );
+
+        let diff = get_unstaged_file_diff(path_str(&repo_path), "file.txt").unwrap();
+        assert_eq!(diff.hunks.len(), 1);
+
+        stage_hunks(
+            path_str(&repo_path),
+            &diff,
+            &[HunkSelection { hunk_index: 0, line_indices: None }],
+        )
+        .unwrap();
+
+        assert_eq!(run_git(&repo_path, ["diff", "--name-only"]), "");
+    }
+
+    #[test]
+    fn stage_part_of_untracked_file() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "base.txt", "base\n", "base");
+        write_file(&repo_path, "new.txt", "keep\nskip\n");
+
+        let diff = get_unstaged_file_diff(path_str(&repo_path), "new.txt").unwrap();
+        assert!(diff.is_new);
+        let keep_index = diff.hunks[0]
+            .lines
+            .iter()
+            .position(|line| line.content == "keep\n")
+            .unwrap();
+        stage_hunks(
+            path_str(&repo_path),
+            &diff,
+            &[HunkSelection { hunk_index: 0, line_indices: Some(vec![keep_index]) }],
+        )
+        .unwrap();
+
+        assert_eq!(run_git(&repo_path, ["show", ":new.txt"]), "keep\n");
+    }
+
+    #[test]
+    fn stage_part_of_intent_to_add_file() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "base.txt", "base\n", "base");
+        write_file(&repo_path, "new.txt", "keep\nskip\n");
+        run_git(&repo_path, ["add", "-N", "new.txt"]);
+
+        let diff = get_unstaged_file_diff(path_str(&repo_path), "new.txt").unwrap();
+        let keep_index = diff.hunks[0]
+            .lines
+            .iter()
+            .position(|line| line.content == "keep\n")
+            .unwrap();
+        stage_hunks(
+            path_str(&repo_path),
+            &diff,
+            &[HunkSelection { hunk_index: 0, line_indices: Some(vec![keep_index]) }],
+        )
+        .unwrap();
+
+        assert_eq!(run_git(&repo_path, ["show", ":new.txt"]), "keep\n");
+    }
+}
+
+#[cfg(test)]
+mod discard_tests {
+    use super::test_support::*;
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn discard_file_to_index_keeps_staged_version_and_backs_up_worktree() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        write_file(&repo_path, "file.txt", "staged\n");
+        run_git(&repo_path, ["add", "file.txt"]);
+        write_file(&repo_path, "file.txt", "unstaged\n");
+
+        let result =
+            discard_files(path_str(&repo_path), &["file.txt".to_string()], DiscardTarget::Index)
+                .unwrap();
+
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "staged\n");
+        let backup = &result.backups[0];
+        let blob_sha = backup.worktree_sha.as_deref().unwrap();
+        assert_eq!(run_git(&repo_path, ["cat-file", "-p", blob_sha]), "unstaged\n");
+    }
+
+    #[test]
+    fn discard_file_to_head_resets_index_and_restore_undoes_it() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        write_file(&repo_path, "file.txt", "staged\n");
+        run_git(&repo_path, ["add", "file.txt"]);
+        write_file(&repo_path, "file.txt", "unstaged\n");
+
+        let result =
+            discard_files(path_str(&repo_path), &["file.txt".to_string()], DiscardTarget::Head)
+                .unwrap();
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "base\n");
+        assert!(run_git(&repo_path, ["status", "--short"]).trim().is_empty());
+
+        restore_discard_backups(path_str(&repo_path), &result.backups).unwrap();
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "unstaged\n");
+        assert_eq!(run_git(&repo_path, ["show", ":file.txt"]), "staged\n");
+    }
+
+    #[test]
+    fn discard_untracked_file_removes_it_and_restore_brings_it_back() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        write_file(&repo_path, "scratch.txt", "notes\n");
+
+        let result =
+            discard_files(path_str(&repo_path), &["scratch.txt".to_string()], DiscardTarget::Head)
+                .unwrap();
+        assert!(!repo_path.join("scratch.txt").exists());
+
+        restore_discard_backups(path_str(&repo_path), &result.backups).unwrap();
+        assert_eq!(fs::read_to_string(repo_path.join("scratch.txt")).unwrap(), "notes\n");
+    }
+
+    #[test]
+    fn discard_single_line_keeps_rest_of_hunk() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "a\nb\nc\n", "base");
+        write_file(&repo_path, "file.txt", "a\ndebug\nb\nkeep\nc\n");
+
+        let diff = get_unstaged_file_diff(path_str(&repo_path), "file.txt").unwrap();
+        let debug_index = diff.hunks[0]
+            .lines
+            .iter()
+            .position(|line| line.content == "debug\n")
+            .unwrap();
+
+        discard_hunks(
+            path_str(&repo_path),
+            &diff,
+            &[HunkSelection { hunk_index: 0, line_indices: Some(vec![debug_index]) }],
+            DiscardTarget::Index,
+        )
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "a\nb\nkeep\nc\n");
+    }
+
+    const BASE: &str = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n";
+    const STAGED: &str = "1\nTWO\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n";
+
+    /// Line 2 staged, then edited again, with an unstaged change on line 14.
+    fn setup_staged_then_edited() -> (tempfile::TempDir, std::path::PathBuf) {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", BASE, "base");
+        write_file(&repo_path, "file.txt", STAGED);
+        run_git(&repo_path, ["add", "file.txt"]);
+        write_file(
+            &repo_path,
+            "file.txt",
+            "1\nTWO!\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\nFOURTEEN\n15\n",
+        );
+        (temp, repo_path)
+    }
+
+    #[test]
+    fn discard_hunk_to_index_keeps_staged_version() {
+        let (_temp, repo_path) = setup_staged_then_edited();
+        let diff = get_unstaged_file_diff(path_str(&repo_path), "file.txt").unwrap();
+        assert_eq!(diff.hunks.len(), 2);
+
+        discard_hunks(
+            path_str(&repo_path),
+            &diff,
+            &[HunkSelection { hunk_index: 0, line_indices: None }],
+            DiscardTarget::Index,
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(repo_path.join("file.txt")).unwrap(),
+            "1\nTWO\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\nFOURTEEN\n15\n"
+        );
+        assert_eq!(run_git(&repo_path, ["show", ":file.txt"]), STAGED);
+    }
+
+    #[test]
+    fn discard_hunk_to_head_reverts_index_and_worktree() {
+        let (_temp, repo_path) = setup_staged_then_edited();
+        let diff = get_file_diff(path_str(&repo_path), "file.txt", false).unwrap();
+        assert_eq!(diff.hunks.len(), 2);
+
+        discard_hunks(
+            path_str(&repo_path),
+            &diff,
+            &[HunkSelection { hunk_index: 0, line_indices: None }],
+            DiscardTarget::Head,
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(repo_path.join("file.txt")).unwrap(),
+            "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\nFOURTEEN\n15\n"
+        );
+        assert_eq!(run_git(&repo_path, ["show", ":file.txt"]), BASE);
+    }
+
+    #[test]
+    fn discard_new_file_to_head_removes_it_everywhere() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "base.txt", "base\n", "base");
+        write_file(&repo_path, "new.txt", "new\n");
+        run_git(&repo_path, ["add", "new.txt"]);
+
+        let diff = get_file_diff(path_str(&repo_path), "new.txt", false).unwrap();
+        discard_hunks(
+            path_str(&repo_path),
+            &diff,
+            &[HunkSelection { hunk_index: 0, line_indices: None }],
+            DiscardTarget::Head,
+        )
+        .unwrap();
+
+        assert!(!repo_path.join("new.txt").exists());
+        assert_eq!(run_git(&repo_path, ["status", "--porcelain"]), "");
+    }
+}
+
+#[cfg(test)]
+mod conflict_tests {
+    use super::test_support::*;
+    use super::*;
+    use std::fs;
+
+    fn setup_merge_conflict() -> (tempfile::TempDir, std::path::PathBuf) {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "shared.txt", "base\n", "base");
+        run_git(&repo_path, ["checkout", "-b", "feature"]);
+        commit_file(&repo_path, "shared.txt", "feature\n", "feature change");
+        run_git(&repo_path, ["checkout", "main"]);
+        commit_file(&repo_path, "shared.txt", "main\n", "main change");
+
+        let output = std::process::Command::new("git")
+            .args(["merge", "feature"])
+            .current_dir(&repo_path)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        (temp, repo_path)
+    }
+
+    #[test]
+    fn lists_conflicts_with_all_three_sides() {
+        let (_temp, repo_path) = setup_merge_conflict();
+
+        let state = list_conflicts(path_str(&repo_path)).unwrap();
+        assert_eq!(state.operation, RepoOperation::Merge);
+        assert_eq!(state.conflicts.len(), 1);
+        assert_eq!(state.conflicts[0].path, "shared.txt");
+        assert!(state.conflicts[0].has_base && state.conflicts[0].has_ours && state.conflicts[0].has_theirs);
+
+        let detail = get_conflict_detail(path_str(&repo_path), "shared.txt").unwrap();
+        assert_eq!(detail.base.as_deref(), Some("base\n"));
+        assert_eq!(detail.ours.as_deref(), Some("main\n"));
+        assert_eq!(detail.theirs.as_deref(), Some("feature\n"));
+        assert!(detail.merged.unwrap().contains("<<<<<<<"));
+    }
+
+    #[test]
+    fn continue_refuses_while_conflicts_remain() {
+        let (_temp, repo_path) = setup_merge_conflict();
+        match continue_operation(path_str(&repo_path)) {
+            Err(GitError::MergeConflict) => {}
+            other => panic!("Expected MergeConflict, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resolve_and_continue_merge_creates_merge_commit() {
+        let (_temp, repo_path) = setup_merge_conflict();
+
+        resolve_conflict(path_str(&repo_path), "shared.txt", Some("resolved\n".to_string())).unwrap();
+        let state = continue_operation(path_str(&repo_path)).unwrap();
+        assert_eq!(state.operation, RepoOperation::None);
+        assert!(state.conflicts.is_empty());
+
+        let parents = run_git(&repo_path, ["rev-list", "--parents", "-n", "1", "HEAD"]);
+        assert_eq!(parents.split_whitespace().count(), 3);
+        assert_eq!(fs::read_to_string(repo_path.join("shared.txt")).unwrap(), "resolved\n");
+    }
+
+    #[test]
+    fn abort_merge_restores_clean_state() {
+        let (_temp, repo_path) = setup_merge_conflict();
+
+        let state = abort_operation(path_str(&repo_path)).unwrap();
+        assert_eq!(state.operation, RepoOperation::None);
+        assert_eq!(fs::read_to_string(repo_path.join("shared.txt")).unwrap(), "main\n");
+    }
+
+    #[test]
+    fn skip_drops_a_conflicted_rebase_step() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "shared.txt", "base\n", "base");
+        run_git(&repo_path, ["checkout", "-b", "feature"]);
+        commit_file(&repo_path, "shared.txt", "feature\n", "feature change");
+        run_git(&repo_path, ["checkout", "main"]);
+        commit_file(&repo_path, "shared.txt", "main\n", "main change");
+        run_git(&repo_path, ["checkout", "feature"]);
+
+        let output = std::process::Command::new("git")
+            .args(["rebase", "main"])
+            .current_dir(&repo_path)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+
+        let state = skip_operation(path_str(&repo_path)).unwrap();
+        assert_eq!(state.operation, RepoOperation::None);
+        assert!(state.conflicts.is_empty());
+        assert_eq!(
+            run_git(&repo_path, ["rev-parse", "HEAD"]),
+            run_git(&repo_path, ["rev-parse", "main"])
+        );
+        assert_eq!(fs::read_to_string(repo_path.join("shared.txt")).unwrap(), "main\n");
+    }
+
+    #[test]
+    fn ship_can_pause_on_rebase_conflict() {
+        let temp = tempfile::tempdir().unwrap();
+        let remote_path = temp.path().join("remote.git");
+        run_git(temp.path(), ["init", "--bare", path_str(&remote_path)]);
+
+        let (_repo_temp, repo_path) = init_test_repo();
+        run_git(&repo_path, ["remote", "add", "origin", path_str(&remote_path)]);
+        commit_file(&repo_path, "shared.txt", "base\n", "base");
+        run_git(&repo_path, ["push", "-u", "origin", "main"]);
+
+        let other_path = temp.path().join("other");
+        run_git(
+            temp.path(),
+            ["clone", "--branch", "main", path_str(&remote_path), path_str(&other_path)],
+        );
+        run_git(&other_path, ["config", "user.name", "Remote User"]);
+        run_git(&other_path, ["config", "user.email", "remote@example.com"]);
+        commit_file(&other_path, "shared.txt", "remote\n", "remote change");
+        run_git(&other_path, ["push", "origin", "main"]);
+
+        run_git(&repo_path, ["fetch", "origin"]);
+        commit_file(&repo_path, "shared.txt", "local\n", "local change");
+
+        let options = ShipOptions {
+            pause_on_conflict: true,
+            ..Default::default()
+        };
+        match ship_with_options(path_str(&repo_path), &options) {
+            Err(GitError::RebaseConflict(message)) => assert!(message.contains("paused")),
+            other => panic!("Expected RebaseConflict, got: {:?}", other),
+        }
+
+        let state = list_conflicts(path_str(&repo_path)).unwrap();
+        assert_eq!(state.operation, RepoOperation::Rebase);
+        assert_eq!(state.conflicts.len(), 1);
+
+        resolve_conflict(path_str(&repo_path), "shared.txt", Some("both\n".to_string())).unwrap();
+        let state = continue_operation(path_str(&repo_path)).unwrap();
+        assert_eq!(state.operation, RepoOperation::None);
+
+        let log = run_git(&repo_path, ["log", "--format=%s"]);
+        assert!(log.starts_with("local change\nremote change\n"));
+    }
+}
+
+#[cfg(test)]
+mod health_tests {
+    use super::test_support::*;
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn clean_repo_is_healthy() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+
+        let health = check_repo_health(path_str(&repo_path)).unwrap();
+        assert!(health.healthy);
+        assert_eq!(health.operation, RepoOperation::None);
+
+        let status = get_status(path_str(&repo_path)).unwrap();
+        assert_eq!(status.operation, RepoOperation::None);
+        assert!(status.rebase_progress.is_none());
+        assert!(!status.index_locked);
+    }
+
+    #[test]
+    fn stale_index_lock_is_reported_and_removable() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+
+        let lock_path = repo_path.join(".git").join("index.lock");
+        let lock = std::fs::File::create(&lock_path).unwrap();
+        lock.set_modified(SystemTime::now() - Duration::from_secs(3600)).unwrap();
+        drop(lock);
+
+        assert!(get_status(path_str(&repo_path)).unwrap().index_locked);
+
+        let health = check_repo_health(path_str(&repo_path)).unwrap();
+        assert!(!health.healthy);
+        let action = RecoveryAction::RemoveLockFile { path: "index.lock".to_string() };
+        assert_eq!(health.issues[0].actions, vec![action.clone()]);
+
+        let health = recover_repo(path_str(&repo_path), &action).unwrap();
+        assert!(health.healthy);
+        assert!(!lock_path.exists());
+    }
+
+    #[test]
+    fn remove_lock_file_rejects_paths_outside_git_dir() {
+        let (_temp, repo_path) = init_test_repo();
+        let action = RecoveryAction::RemoveLockFile { path: "../file.lock".to_string() };
+        assert!(recover_repo(path_str(&repo_path), &action).is_err());
+    }
+
+    #[test]
+    fn conflicted_rebase_reports_progress_and_abort_recovers() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "shared.txt", "base\n", "base");
+        run_git(&repo_path, ["checkout", "-b", "upstream"]);
+        commit_file(&repo_path, "shared.txt", "upstream\n", "upstream change");
+        run_git(&repo_path, ["checkout", "main"]);
+        commit_file(&repo_path, "shared.txt", "local\n", "local change");
+        commit_file(&repo_path, "other.txt", "other\n", "other change");
+
+        let output = std::process::Command::new("git")
+            .args(["rebase", "upstream"])
+            .current_dir(&repo_path)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+
+        let status = get_status(path_str(&repo_path)).unwrap();
+        assert_eq!(status.operation, RepoOperation::Rebase);
+        let progress = status.rebase_progress.unwrap();
+        assert_eq!((progress.current, progress.total), (1, 2));
+        assert_eq!(progress.head_name.as_deref(), Some("main"));
+
+        let health = check_repo_health(path_str(&repo_path)).unwrap();
+        assert!(health.issues[0].message.contains("step 1 of 2"));
+        assert_eq!(health.issues[0].actions, vec![RecoveryAction::AbortOperation]);
+
+        let health = recover_repo(path_str(&repo_path), &RecoveryAction::AbortOperation).unwrap();
+        assert!(health.healthy);
+        assert_eq!(get_status(path_str(&repo_path)).unwrap().branch, "main");
+    }
+}
+
+#[cfg(test)]
+mod undo_save_tests {
+    use super::test_support::*;
+    use super::*;
+
+    #[test]
+    fn undo_save_keeps_changes_staged() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        let base_head = run_git(&repo_path, ["rev-parse", "HEAD"]).trim().to_string();
+        commit_file(&repo_path, "file.txt", "changed\n", "quick save");
+
+        let result = undo_save(path_str(&repo_path)).unwrap();
+        assert_eq!(result.head_sha, base_head);
+        assert_eq!(result.message.trim(), "quick save");
+        assert_eq!(run_git(&repo_path, ["rev-parse", "HEAD"]).trim(), base_head);
+        assert_eq!(run_git(&repo_path, ["diff", "--cached", "--name-only"]).trim(), "file.txt");
+    }
+
+    #[test]
+    fn undo_save_refuses_initial_commit() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        assert!(undo_save(path_str(&repo_path)).is_err());
+    }
+
+    #[test]
+    fn undo_and_amend_refuse_pushed_commit() {
+        let (temp, repo_path) = init_test_repo();
+        let remote_path = temp.path().join("remote.git");
+        run_git(temp.path(), ["init", "--bare", path_str(&remote_path)]);
+        run_git(&repo_path, ["remote", "add", "origin", path_str(&remote_path)]);
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        commit_file(&repo_path, "file.txt", "shipped\n", "shipped");
+        run_git(&repo_path, ["push", "-u", "origin", "main"]);
+
+        match undo_save(path_str(&repo_path)) {
+            Err(GitError::AlreadyPushed(_)) => {}
+            other => panic!("Expected AlreadyPushed, got: {:?}", other),
+        }
+        match amend_save(path_str(&repo_path), Some("reworded".to_string())) {
+            Err(GitError::AlreadyPushed(_)) => {}
+            other => panic!("Expected AlreadyPushed, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn amend_save_folds_changes_and_message_into_head() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        commit_file(&repo_path, "file.txt", "first\n", "quick save");
+        write_file(&repo_path, "extra.txt", "extra\n");
+
+        let result = amend_save(path_str(&repo_path), Some("Proper message".to_string())).unwrap();
+        assert_eq!(result.message, "Proper message");
+        assert_eq!(result.files_committed, 2);
+        assert_eq!(run_git(&repo_path, ["rev-list", "--count", "HEAD"]).trim(), "2");
+        assert_eq!(
+            run_git(&repo_path, ["show", "--name-only", "--format=", "HEAD"]).trim(),
+            "extra.txt\nfile.txt"
+        );
+    }
+
+    #[test]
+    fn amend_save_records_the_current_committer() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        run_git(&repo_path, ["config", "user.name", "Amending User"]);
+        run_git(&repo_path, ["config", "user.email", "amend@example.com"]);
+
+        amend_save(path_str(&repo_path), Some("Reworded".to_string())).unwrap();
+        assert_eq!(
+            run_git(&repo_path, ["log", "-1", "--format=%an|%cn <%ce>"]).trim(),
+            "Test User|Amending User <amend@example.com>"
+        );
+    }
+
+    #[test]
+    fn amend_save_without_changes_or_message_is_nothing_to_commit() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        match amend_save(path_str(&repo_path), None) {
+            Err(GitError::NothingToCommit) => {}
+            other => panic!("Expected NothingToCommit, got: {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod rebase_tests {
+    use super::test_support::*;
+    use super::*;
+
+    fn init_with_remote() -> (tempfile::TempDir, std::path::PathBuf) {
+        let (temp, repo_path) = init_test_repo();
+        let remote_path = temp.path().join("remote.git");
+        run_git(temp.path(), ["init", "--bare", path_str(&remote_path)]);
+        run_git(&repo_path, ["remote", "add", "origin", path_str(&remote_path)]);
+        commit_file(&repo_path, "base.txt", "base\n", "base");
+        run_git(&repo_path, ["push", "-u", "origin", "main"]);
+        (temp, repo_path)
+    }
+
+    fn log_messages(repo_path: &Path) -> Vec<String> {
+        run_git(repo_path, ["log", "--format=%B%x00", "origin/main..HEAD"])
+            .split('\0')
+            .map(|message| message.trim().to_string())
+            .filter(|message| !message.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn plan_lists_unpushed_commits_oldest_first() {
+        let (_temp, repo_path) = init_with_remote();
+        commit_file(&repo_path, "a.txt", "a\n", "quick save a");
+        commit_file(&repo_path, "b.txt", "b\n", "quick save b");
+
+        let plan = plan_rebase(path_str(&repo_path)).unwrap();
+        let messages: Vec<&str> = plan.steps.iter().map(|s| s.commit.message.trim()).collect();
+        assert_eq!(messages, vec!["quick save a", "quick save b"]);
+        assert!(plan.steps.iter().all(|s| s.action == RebaseAction::Pick));
+        assert_eq!(plan.onto, run_git(&repo_path, ["rev-parse", "origin/main"]).trim());
+    }
+
+    #[test]
+    fn execute_squashes_rewords_and_drops() {
+        let (_temp, repo_path) = init_with_remote();
+        commit_file(&repo_path, "a.txt", "a\n", "quick save a");
+        commit_file(&repo_path, "b.txt", "b\n", "quick save b");
+        commit_file(&repo_path, "c.txt", "c\n", "quick save c");
+        commit_file(&repo_path, "d.txt", "d\n", "quick save d");
+
+        let mut plan = plan_rebase(path_str(&repo_path)).unwrap();
+        plan.steps[1].action = RebaseAction::Squash;
+        plan.steps[2].action = RebaseAction::Drop;
+        plan.steps[3].action = RebaseAction::Reword;
+        plan.steps[3].new_message = Some("Add d".to_string());
+
+        let result = execute_rebase(path_str(&repo_path), &plan).unwrap();
+        assert_eq!(result.commits, 2);
+        assert_eq!(result.head_sha, run_git(&repo_path, ["rev-parse", "HEAD"]).trim());
+        assert_eq!(log_messages(&repo_path), vec!["Add d", "quick save a\n\nquick save b"]);
+        assert!(!repo_path.join("c.txt").exists());
+        assert!(repo_path.join("b.txt").exists());
+    }
+
+    #[test]
+    fn execute_fixup_with_new_message_and_reorder() {
+        let (_temp, repo_path) = init_with_remote();
+        commit_file(&repo_path, "a.txt", "a\n", "quick save a");
+        commit_file(&repo_path, "b.txt", "b\n", "quick save b");
+        commit_file(&repo_path, "a.txt", "a2\n", "quick save a again");
+
+        let mut plan = plan_rebase(path_str(&repo_path)).unwrap();
+        let mut last = plan.steps.remove(2);
+        last.action = RebaseAction::Fixup;
+        last.new_message = Some("Add a".to_string());
+        plan.steps.insert(1, last);
+
+        execute_rebase(path_str(&repo_path), &plan).unwrap();
+        assert_eq!(log_messages(&repo_path), vec!["quick save b", "Add a"]);
+        assert_eq!(run_git(&repo_path, ["show", "HEAD~1:a.txt"]), "a2\n");
+    }
+
+    #[test]
+    fn execute_pauses_on_conflict() {
+        let (_temp, repo_path) = init_with_remote();
+        commit_file(&repo_path, "a.txt", "one\n", "first");
+        commit_file(&repo_path, "a.txt", "two\n", "second");
+        let original_head = run_git(&repo_path, ["rev-parse", "HEAD"]).trim().to_string();
+
+        let mut plan = plan_rebase(path_str(&repo_path)).unwrap();
+        plan.steps.swap(0, 1);
+
+        match execute_rebase(path_str(&repo_path), &plan) {
+            Err(GitError::RebaseConflict(_)) => {}
+            other => panic!("Expected RebaseConflict, got: {:?}", other),
+        }
+        assert_eq!(list_conflicts(path_str(&repo_path)).unwrap().operation, RepoOperation::Rebase);
+        assert!(repo_path.join(".git/vibogit-rebase").exists());
+
+        abort_operation(path_str(&repo_path)).unwrap();
+        assert_eq!(run_git(&repo_path, ["rev-parse", "HEAD"]).trim(), original_head);
+        assert!(!repo_path.join(".git/vibogit-rebase").exists());
+    }
+
+    #[test]
+    fn execute_rejects_stale_plan() {
+        let (_temp, repo_path) = init_with_remote();
+        commit_file(&repo_path, "a.txt", "a\n", "quick save a");
+        let plan = plan_rebase(path_str(&repo_path)).unwrap();
+        commit_file(&repo_path, "b.txt", "b\n", "quick save b");
+
+        assert!(execute_rebase(path_str(&repo_path), &plan).is_err());
+        assert_eq!(run_git(&repo_path, ["rev-list", "--count", "origin/main..HEAD"]).trim(), "2");
+    }
+
+    #[test]
+    fn execute_reports_dirty_tree_as_rebase_failure() {
+        let (_temp, repo_path) = init_with_remote();
+        commit_file(&repo_path, "a.txt", "a\n", "quick save a");
+        commit_file(&repo_path, "b.txt", "b\n", "quick save b");
+        write_file(&repo_path, "a.txt", "dirty\n");
+
+        let mut plan = plan_rebase(path_str(&repo_path)).unwrap();
+        plan.steps[1].action = RebaseAction::Squash;
+
+        match execute_rebase(path_str(&repo_path), &plan) {
+            Err(GitError::Git2(message)) => assert!(message.starts_with("Rebase failed:"), "{}", message),
+            other => panic!("Expected a rebase failure, got: {:?}", other),
+        }
+        assert_eq!(list_conflicts(path_str(&repo_path)).unwrap().operation, RepoOperation::None);
+    }
+}
+
+#[cfg(test)]
+mod stash_tests {
+    use super::test_support::*;
+    use super::*;
+    use std::fs;
+
+    fn init_with_base() -> (tempfile::TempDir, std::path::PathBuf) {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        (temp, repo_path)
+    }
+
+    #[test]
+    fn save_with_untracked_lists_and_diffs_stash() {
+        let (_temp, repo_path) = init_with_base();
+        write_file(&repo_path, "file.txt", "changed\n");
+        write_file(&repo_path, "new.txt", "new\n");
+
+        let options = StashOptions {
+            include_untracked: true,
+            keep_index: false,
+        };
+        stash_save_with_options(path_str(&repo_path), Some("wip work".to_string()), &options).unwrap();
+        assert!(!repo_path.join("new.txt").exists());
+
+        let stashes = stash_list(path_str(&repo_path)).unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert_eq!(stashes[0].index, 0);
+        assert_eq!(stashes[0].message, "On main: wip work");
+        assert_eq!(stashes[0].branch.as_deref(), Some("main"));
+
+        let files = stash_diff(path_str(&repo_path), 0).unwrap();
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["file.txt", "new.txt"]);
+        assert!(files[0].hunks[0].lines.iter().any(|l| l.line_type == "add" && l.content == "changed\n"));
+    }
+
+    #[test]
+    fn save_with_keep_index_leaves_staged_changes() {
+        let (_temp, repo_path) = init_with_base();
+        write_file(&repo_path, "file.txt", "staged\n");
+        run_git(&repo_path, ["add", "file.txt"]);
+
+        let options = StashOptions {
+            include_untracked: false,
+            keep_index: true,
+        };
+        stash_save_with_options(path_str(&repo_path), None, &options).unwrap();
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "staged\n");
+        assert_eq!(stash_list(path_str(&repo_path)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn apply_and_drop_any_index() {
+        let (_temp, repo_path) = init_with_base();
+        write_file(&repo_path, "file.txt", "older\n");
+        stash_save(path_str(&repo_path), Some("older".to_string())).unwrap();
+        write_file(&repo_path, "file.txt", "newer\n");
+        stash_save(path_str(&repo_path), Some("newer".to_string())).unwrap();
+
+        let result = stash_apply(path_str(&repo_path), 1, false).unwrap();
+        assert!(result.conflicts.is_empty());
+        assert!(!result.dropped);
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "older\n");
+
+        stash_drop(path_str(&repo_path), 1).unwrap();
+        let stashes = stash_list(path_str(&repo_path)).unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert_eq!(stashes[0].message, "On main: newer");
+        assert!(stash_drop(path_str(&repo_path), 3).is_err());
+    }
+
+    #[test]
+    fn conflicting_pop_reports_conflicts_and_keeps_stash() {
+        let (_temp, repo_path) = init_with_base();
+        write_file(&repo_path, "file.txt", "stashed\n");
+        stash_save(path_str(&repo_path), None).unwrap();
+        commit_file(&repo_path, "file.txt", "committed\n", "diverge");
+
+        let result = stash_apply(path_str(&repo_path), 0, true).unwrap();
+        assert!(!result.dropped);
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].path, "file.txt");
+        assert_eq!(stash_list(path_str(&repo_path)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn branch_from_stash_checks_out_stash_base() {
+        let (_temp, repo_path) = init_with_base();
+        let base = run_git(&repo_path, ["rev-parse", "HEAD"]).trim().to_string();
+        write_file(&repo_path, "file.txt", "stashed\n");
+        stash_save(path_str(&repo_path), None).unwrap();
+        commit_file(&repo_path, "file.txt", "committed\n", "diverge");
+
+        stash_branch(path_str(&repo_path), 0, "from-stash").unwrap();
+        assert_eq!(run_git(&repo_path, ["branch", "--show-current"]).trim(), "from-stash");
+        assert_eq!(run_git(&repo_path, ["rev-parse", "HEAD"]).trim(), base);
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "stashed\n");
+        assert!(stash_list(path_str(&repo_path)).unwrap().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod branch_tests {
+    use super::test_support::*;
+    use super::*;
+
+    fn init_with_feature() -> (tempfile::TempDir, std::path::PathBuf) {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        run_git(&repo_path, ["checkout", "-b", "feature"]);
+        commit_file(&repo_path, "feature.txt", "feature\n", "feature work");
+        run_git(&repo_path, ["checkout", "main"]);
+        (temp, repo_path)
+    }
+
+    fn branch_list(repo_path: &Path) -> String {
+        run_git(repo_path, ["branch", "--format=%(refname:short)"]).trim().to_string()
+    }
+
+    #[test]
+    fn delete_refuses_unmerged_branch_unless_forced() {
+        let (_temp, repo_path) = init_with_feature();
+
+        match delete_branch(path_str(&repo_path), "feature", false) {
+            Err(GitError::BranchNotMerged(name)) => assert_eq!(name, "feature"),
+            other => panic!("Expected BranchNotMerged, got: {:?}", other),
+        }
+        assert!(delete_branch(path_str(&repo_path), "main", true).is_err());
+
+        delete_branch(path_str(&repo_path), "feature", true).unwrap();
+        assert_eq!(branch_list(&repo_path), "main");
+    }
+
+    #[test]
+    fn rename_keeps_tracking_and_upstream_can_be_unset() {
+        let (temp, repo_path) = init_with_feature();
+        let remote_path = temp.path().join("remote.git");
+        run_git(temp.path(), ["init", "--bare", path_str(&remote_path)]);
+        run_git(&repo_path, ["remote", "add", "origin", path_str(&remote_path)]);
+        run_git(&repo_path, ["push", "origin", "feature"]);
+
+        set_upstream(path_str(&repo_path), "feature", Some("origin/feature")).unwrap();
+        rename_branch(path_str(&repo_path), "feature", "renamed").unwrap();
+        assert_eq!(
+            run_git(&repo_path, ["rev-parse", "--abbrev-ref", "renamed@{upstream}"]).trim(),
+            "origin/feature"
+        );
+
+        set_upstream(path_str(&repo_path), "renamed", None).unwrap();
+        let repo = Repository::open(&repo_path).unwrap();
+        assert!(repo.find_branch("renamed", git2::BranchType::Local).unwrap().upstream().is_err());
+    }
+
+    #[test]
+    fn merge_modes() {
+        let (_temp, repo_path) = init_with_feature();
+        let feature = run_git(&repo_path, ["rev-parse", "feature"]).trim().to_string();
+
+        run_git(&repo_path, ["branch", "for-no-ff", "main"]);
+        run_git(&repo_path, ["branch", "for-squash", "main"]);
+
+        let result = merge_branch(path_str(&repo_path), "feature", MergeMode::Ff).unwrap();
+        assert!(result.fast_forward);
+        assert_eq!(result.head_sha, feature);
+        assert!(merge_branch(path_str(&repo_path), "feature", MergeMode::Ff).unwrap().up_to_date);
+
+        run_git(&repo_path, ["checkout", "for-no-ff"]);
+        let result = merge_branch(path_str(&repo_path), "feature", MergeMode::NoFf).unwrap();
+        assert!(!result.fast_forward);
+        assert_eq!(run_git(&repo_path, ["rev-list", "--parents", "-n1", "HEAD"]).split_whitespace().count(), 3);
+
+        run_git(&repo_path, ["checkout", "for-squash"]);
+        merge_branch(path_str(&repo_path), "feature", MergeMode::Squash).unwrap();
+        assert_eq!(run_git(&repo_path, ["rev-list", "--parents", "-n1", "HEAD"]).split_whitespace().count(), 2);
+        assert!(repo_path.join("feature.txt").exists());
+        assert!(run_git(&repo_path, ["status", "--porcelain"]).trim().is_empty());
+    }
+
+    #[test]
+    fn merge_reports_conflicts_and_ff_only_refuses_divergence() {
+        let (_temp, repo_path) = init_with_feature();
+        run_git(&repo_path, ["checkout", "feature"]);
+        commit_file(&repo_path, "file.txt", "feature side\n", "feature edit");
+        run_git(&repo_path, ["checkout", "main"]);
+        commit_file(&repo_path, "file.txt", "main side\n", "main edit");
+
+        assert!(merge_branch(path_str(&repo_path), "feature", MergeMode::FfOnly).is_err());
+
+        let result = merge_branch(path_str(&repo_path), "feature", MergeMode::Ff).unwrap();
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].path, "file.txt");
+        assert_eq!(list_conflicts(path_str(&repo_path)).unwrap().operation, RepoOperation::Merge);
+    }
+
+    #[test]
+    fn conflicting_squash_is_finished_by_save() {
+        let (_temp, repo_path) = init_with_feature();
+        run_git(&repo_path, ["checkout", "feature"]);
+        commit_file(&repo_path, "file.txt", "feature side\n", "feature edit");
+        run_git(&repo_path, ["checkout", "main"]);
+        commit_file(&repo_path, "file.txt", "main side\n", "main edit");
+        let main_head = run_git(&repo_path, ["rev-parse", "HEAD"]).trim().to_string();
+
+        let result = merge_branch(path_str(&repo_path), "feature", MergeMode::Squash).unwrap();
+        assert_eq!(result.head_sha, main_head);
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].path, "file.txt");
+
+        write_file(&repo_path, "file.txt", "resolved\n");
+        let result = save(path_str(&repo_path), None).unwrap();
+        assert!(result.message.starts_with("Squashed commit of the following"));
+        let parents = run_git(&repo_path, ["rev-list", "--parents", "-n1", "HEAD"]);
+        assert_eq!(parents.split_whitespace().skip(1).collect::<Vec<_>>(), vec![main_head.as_str()]);
+        assert_eq!(run_git(&repo_path, ["show", "HEAD:file.txt"]), "resolved\n");
+        assert!(!repo_path.join(".git/SQUASH_MSG").exists());
+        assert!(!repo_path.join(".git/vibogit-squash").exists());
+    }
+
+    #[test]
+    fn save_ignores_squash_message_without_pending_squash() {
+        let (_temp, repo_path) = init_with_feature();
+        std::fs::write(repo_path.join(".git/SQUASH_MSG"), "Stale squash\n").unwrap();
+
+        write_file(&repo_path, "other.txt", "other\n");
+        let result = save(path_str(&repo_path), None).unwrap();
+        assert_eq!(result.message, "Update 1 file");
+        assert!(repo_path.join(".git/SQUASH_MSG").exists());
+    }
+
+    #[test]
+    fn delete_merged_branches_keeps_unmerged_and_default() {
+        let (_temp, repo_path) = init_with_feature();
+        run_git(&repo_path, ["branch", "merged-one", "main"]);
+        run_git(&repo_path, ["checkout", "-b", "merged-two"]);
+        commit_file(&repo_path, "two.txt", "two\n", "two");
+        run_git(&repo_path, ["checkout", "main"]);
+        run_git(&repo_path, ["merge", "--ff-only", "merged-two"]);
+
+        let mut deleted = delete_merged_branches(path_str(&repo_path)).unwrap();
+        deleted.sort();
+        assert_eq!(deleted, vec!["merged-one", "merged-two"]);
+        assert_eq!(branch_list(&repo_path), "feature\nmain");
+    }
+}
+
+#[cfg(test)]
+mod checkout_tests {
+    use super::test_support::*;
+    use super::*;
+    use std::fs;
+
+    /// `main` and `feature` differ only on the last line of file.txt.
+    fn init_with_branches() -> (tempfile::TempDir, std::path::PathBuf) {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "one\ntwo\nthree\nfour\nfive\n", "base");
+        commit_file(&repo_path, "other.txt", "other\n", "other");
+        run_git(&repo_path, ["checkout", "-b", "feature"]);
+        commit_file(&repo_path, "file.txt", "one\ntwo\nthree\nfour\nFIVE\n", "feature edit");
+        run_git(&repo_path, ["checkout", "main"]);
+        (temp, repo_path)
+    }
+
+    fn current_branch(repo_path: &Path) -> String {
+        run_git(repo_path, ["branch", "--show-current"]).trim().to_string()
+    }
+
+    #[test]
+    fn safe_checkout_refuses_to_overwrite_changes() {
+        let (_temp, repo_path) = init_with_branches();
+        write_file(&repo_path, "file.txt", "ONE\ntwo\nthree\nfour\nfive\n");
+
+        match checkout(path_str(&repo_path), "feature") {
+            Err(GitError::CheckoutConflict(files)) => assert_eq!(files, vec!["file.txt"]),
+            other => panic!("Expected CheckoutConflict, got: {:?}", other),
+        }
+        assert_eq!(current_branch(&repo_path), "main");
+        assert_eq!(
+            fs::read_to_string(repo_path.join("file.txt")).unwrap(),
+            "ONE\ntwo\nthree\nfour\nfive\n"
+        );
+    }
+
+    #[test]
+    fn safe_checkout_keeps_unrelated_changes() {
+        let (_temp, repo_path) = init_with_branches();
+        write_file(&repo_path, "other.txt", "edited\n");
+
+        checkout(path_str(&repo_path), "feature").unwrap();
+        assert_eq!(current_branch(&repo_path), "feature");
+        assert_eq!(fs::read_to_string(repo_path.join("other.txt")).unwrap(), "edited\n");
+        assert_eq!(
+            fs::read_to_string(repo_path.join("file.txt")).unwrap(),
+            "one\ntwo\nthree\nfour\nFIVE\n"
+        );
+    }
+
+    #[test]
+    fn autostash_and_carry_bring_changes_across() {
+        for mode in [CheckoutMode::Autostash, CheckoutMode::Carry] {
+            let (_temp, repo_path) = init_with_branches();
+            write_file(&repo_path, "file.txt", "ONE\ntwo\nthree\nfour\nfive\n");
+
+            let result = checkout_with_options(path_str(&repo_path), "feature", &CheckoutOptions { mode }).unwrap();
+            assert_eq!(result.head, "feature");
+            assert!(result.conflicts.is_empty());
+            assert!(!result.stash_kept);
+            assert_eq!(
+                fs::read_to_string(repo_path.join("file.txt")).unwrap(),
+                "ONE\ntwo\nthree\nfour\nFIVE\n",
+                "mode {:?}",
+                mode
+            );
+            assert!(run_git(&repo_path, ["stash", "list"]).trim().is_empty());
+        }
+    }
+
+    #[test]
+    fn autostash_keeps_staged_changes_staged() {
+        let (_temp, repo_path) = init_with_branches();
+        write_file(&repo_path, "file.txt", "ONE\ntwo\nthree\nfour\nfive\n");
+        run_git(&repo_path, ["add", "file.txt"]);
+
+        let options = CheckoutOptions {
+            mode: CheckoutMode::Autostash,
+        };
+        let result = checkout_with_options(path_str(&repo_path), "feature", &options).unwrap();
+        assert!(!result.stash_kept);
+        assert_eq!(run_git(&repo_path, ["diff", "--cached", "--name-only"]).trim(), "file.txt");
+        assert_eq!(run_git(&repo_path, ["show", ":file.txt"]), "ONE\ntwo\nthree\nfour\nFIVE\n");
+    }
+
+    #[test]
+    fn autostash_reports_conflicts_and_keeps_stash() {
+        let (_temp, repo_path) = init_with_branches();
+        write_file(&repo_path, "file.txt", "one\ntwo\nthree\nfour\nlocal\n");
+
+        let options = CheckoutOptions {
+            mode: CheckoutMode::Autostash,
+        };
+        let result = checkout_with_options(path_str(&repo_path), "feature", &options).unwrap();
+        assert_eq!(current_branch(&repo_path), "feature");
+        assert_eq!(result.conflicts.len(), 1);
+        assert!(result.stash_kept);
+        assert_eq!(run_git(&repo_path, ["stash", "list"]).lines().count(), 1);
+    }
+
+    #[test]
+    fn remote_branch_creates_local_tracking_branch() {
+        let (temp, repo_path) = init_with_branches();
+        let remote_path = temp.path().join("remote.git");
+        run_git(temp.path(), ["init", "--bare", path_str(&remote_path)]);
+        run_git(&repo_path, ["remote", "add", "origin", path_str(&remote_path)]);
+        run_git(&repo_path, ["push", "origin", "main", "feature"]);
+        run_git(&repo_path, ["fetch", "origin"]);
+        run_git(&repo_path, ["branch", "-D", "feature"]);
+
+        let result = checkout_with_options(path_str(&repo_path), "origin/feature", &CheckoutOptions::default()).unwrap();
+        assert_eq!(result.head, "feature");
+        assert_eq!(result.created_branch.as_deref(), Some("feature"));
+        assert_eq!(current_branch(&repo_path), "feature");
+        assert_eq!(
+            run_git(&repo_path, ["rev-parse", "--abbrev-ref", "feature@{upstream}"]).trim(),
+            "origin/feature"
+        );
+
+        // A second checkout reuses the tracking branch
+        run_git(&repo_path, ["checkout", "main"]);
+        let result = checkout_with_options(path_str(&repo_path), "origin/feature", &CheckoutOptions::default()).unwrap();
+        assert_eq!(result.created_branch, None);
+        assert_eq!(current_branch(&repo_path), "feature");
+    }
 }
 
 #[cfg(test)]
-mod ship_tests {
+mod tag_tests {
+    use super::test_support::*;
     use super::*;
-    use std::{
-        fs,
-        path::{Path, PathBuf},
-        process::Command,
-    };
+    use std::fs;
 
-    fn run_git<I, S>(cwd: &Path, args: I) -> std::process::Output
-    where
-        I: IntoIterator<Item = S>,
-        S: AsRef<std::ffi::OsStr>,
-    {
-        let output = Command::new("git").args(args).current_dir(cwd).output().unwrap();
-        assert!(
-            output.status.success(),
-            "git command failed in {}: stdout={} stderr={}",
-            cwd.display(),
-            String::from_utf8_lossy(&output.stdout),
-            String::from_utf8_lossy(&output.stderr)
+    fn init_with_remote() -> (tempfile::TempDir, std::path::PathBuf) {
+        let (temp, repo_path) = init_test_repo();
+        let remote_path = temp.path().join("remote.git");
+        run_git(temp.path(), ["init", "--bare", path_str(&remote_path)]);
+        run_git(&repo_path, ["remote", "add", "origin", path_str(&remote_path)]);
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        (temp, repo_path)
+    }
+
+    fn remote_tags(repo_path: &Path) -> String {
+        run_git(repo_path, ["ls-remote", "--tags", "--refs", "origin"])
+            .lines()
+            .filter_map(|line| line.split("refs/tags/").nth(1))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    #[test]
+    fn create_list_push_and_delete_tags() {
+        let (_temp, repo_path) = init_with_remote();
+        let head = run_git(&repo_path, ["rev-parse", "HEAD"]).trim().to_string();
+
+        let light = create_tag(path_str(&repo_path), "light", None, None).unwrap();
+        assert!(!light.annotated);
+        assert_eq!(light.target_sha, head);
+
+        let annotated = create_tag(path_str(&repo_path), "v1.0.0", Some("HEAD"), Some("First release")).unwrap();
+        assert!(annotated.annotated);
+        assert_eq!(annotated.target_sha, head);
+        assert_eq!(annotated.message.as_deref().map(str::trim), Some("First release"));
+        assert_eq!(annotated.tagger.as_deref(), Some("Test User"));
+
+        let names: Vec<String> = list_tags(path_str(&repo_path)).unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, vec!["light", "v1.0.0"]);
+
+        push_tags(path_str(&repo_path), Some("origin"), Some("v1.0.0")).unwrap();
+        assert_eq!(remote_tags(&repo_path), "v1.0.0");
+        push_tags(path_str(&repo_path), None, None).unwrap();
+        assert_eq!(remote_tags(&repo_path), "light,v1.0.0");
+
+        delete_tag(path_str(&repo_path), "v1.0.0", Some("origin")).unwrap();
+        delete_tag(path_str(&repo_path), "light", None).unwrap();
+        assert!(list_tags(path_str(&repo_path)).unwrap().is_empty());
+        assert_eq!(remote_tags(&repo_path), "light");
+    }
+
+    #[test]
+    fn bump_version_follows_semver() {
+        assert_eq!(bump_version("1.2.3", VersionBump::Patch).unwrap(), "1.2.4");
+        assert_eq!(bump_version("1.2.3", VersionBump::Minor).unwrap(), "1.3.0");
+        assert_eq!(bump_version("1.2.3-beta.1", VersionBump::Major).unwrap(), "2.0.0");
+        assert!(bump_version("1.2", VersionBump::Patch).is_err());
+    }
+
+    #[test]
+    fn release_bumps_manifests_commits_tags_and_ships() {
+        let (_temp, repo_path) = init_with_remote();
+        write_file(&repo_path, "package.json", "{\n  \"name\": \"app\",\n  \"version\": \"1.2.3\"\n}\n");
+        write_file(
+            &repo_path,
+            "src-tauri/Cargo.toml",
+            "[package]\nname = \"app\"\nversion = \"1.2.3\"\n\n[dependencies]\nserde = { version = \"1.2.3\" }\n",
         );
-        output
+        write_file(
+            &repo_path,
+            "src-tauri/Cargo.lock",
+            "[[package]]\nname = \"app\"\nversion = \"1.2.3\"\n\n[[package]]\nname = \"serde\"\nversion = \"1.2.3\"\n",
+        );
+        write_file(&repo_path, "src-tauri/tauri.conf.json", "{ \"version\": \"0.9.0\" }\n");
+        run_git(&repo_path, ["add", "."]);
+        run_git(&repo_path, ["commit", "-m", "manifests"]);
+
+        let options = ReleaseOptions {
+            bump: VersionBump::Minor,
+            ship: true,
+        };
+        let result = release(path_str(&repo_path), &options).unwrap();
+        assert_eq!(result.previous_version, "1.2.3");
+        assert_eq!(result.version, "1.3.0");
+        assert_eq!(result.tag, "v1.3.0");
+        assert_eq!(result.files, vec!["package.json", "src-tauri/Cargo.toml", "src-tauri/Cargo.lock"]);
+        assert!(result.shipped.is_some());
+
+        assert_eq!(
+            fs::read_to_string(repo_path.join("package.json")).unwrap(),
+            "{\n  \"name\": \"app\",\n  \"version\": \"1.3.0\"\n}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(repo_path.join("src-tauri/Cargo.toml")).unwrap(),
+            "[package]\nname = \"app\"\nversion = \"1.3.0\"\n\n[dependencies]\nserde = { version = \"1.2.3\" }\n"
+        );
+        assert_eq!(
+            fs::read_to_string(repo_path.join("src-tauri/Cargo.lock")).unwrap(),
+            "[[package]]\nname = \"app\"\nversion = \"1.3.0\"\n\n[[package]]\nname = \"serde\"\nversion = \"1.2.3\"\n"
+        );
+        assert_eq!(
+            fs::read_to_string(repo_path.join("src-tauri/tauri.conf.json")).unwrap(),
+            "{ \"version\": \"0.9.0\" }\n"
+        );
+        assert_eq!(run_git(&repo_path, ["log", "-1", "--format=%s"]).trim(), "Release 1.3.0");
+        assert_eq!(run_git(&repo_path, ["rev-parse", "v1.3.0^{commit}"]).trim(), result.sha);
+        assert_eq!(remote_tags(&repo_path), "v1.3.0");
     }
 
-    fn run_git_allow_failure<I, S>(cwd: &Path, args: I) -> std::process::Output
-    where
-        I: IntoIterator<Item = S>,
-        S: AsRef<std::ffi::OsStr>,
-    {
-        Command::new("git").args(args).current_dir(cwd).output().unwrap()
+    #[test]
+    fn release_refuses_dirty_tree() {
+        let (_temp, repo_path) = init_with_remote();
+        commit_file(&repo_path, "package.json", "{ \"version\": \"1.0.0\" }\n", "manifest");
+        write_file(&repo_path, "file.txt", "dirty\n");
+
+        let options = ReleaseOptions {
+            bump: VersionBump::Patch,
+            ship: false,
+        };
+        assert!(release(path_str(&repo_path), &options).is_err());
+        assert!(list_tags(path_str(&repo_path)).unwrap().is_empty());
     }
 
-    fn write_file(path: &Path, contents: &str) {
-        fs::write(path, contents).unwrap();
+    #[test]
+    fn release_fails_when_no_manifest_can_be_rewritten() {
+        let (_temp, repo_path) = init_with_remote();
+        commit_file(&repo_path, "package.json", "{ \"version\": \"1.0\\u002e0\" }\n", "manifest");
+        let head = run_git(&repo_path, ["rev-parse", "HEAD"]);
+
+        let options = ReleaseOptions {
+            bump: VersionBump::Patch,
+            ship: false,
+        };
+        assert!(release(path_str(&repo_path), &options).is_err());
+        assert_eq!(run_git(&repo_path, ["rev-parse", "HEAD"]), head);
+        assert!(list_tags(path_str(&repo_path)).unwrap().is_empty());
     }
+}
 
-    fn commit_file(repo_path: &Path, file_name: &str, contents: &str, message: &str) {
-        write_file(&repo_path.join(file_name), contents);
-        run_git(repo_path, ["add", file_name]);
-        run_git(repo_path, ["commit", "-m", message]);
+#[cfg(test)]
+mod remote_tests {
+    use super::test_support::*;
+    use super::*;
+
+    fn init_bare(temp: &tempfile::TempDir, name: &str) -> std::path::PathBuf {
+        let path = temp.path().join(name);
+        run_git(temp.path(), ["init", "--bare", "-b", "main", path_str(&path)]);
+        path
     }
 
-    fn clone_repo_on_branch(remote_path: &Path, destination: &Path, branch: &str) {
-        let output = Command::new("git")
-            .args([
-                "clone",
-                "--branch",
-                branch,
-                remote_path.to_str().unwrap(),
-                destination.to_str().unwrap(),
-            ])
-            .output()
-            .unwrap();
-        assert!(
-            output.status.success(),
-            "git clone --branch failed: stdout={} stderr={}",
-            String::from_utf8_lossy(&output.stdout),
-            String::from_utf8_lossy(&output.stderr)
+    #[test]
+    fn add_rename_set_url_and_remove() {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        let first = init_bare(&temp, "first.git");
+        let second = init_bare(&temp, "second.git");
+
+        add_remote(path_str(&repo_path), "origin", path_str(&first)).unwrap();
+        run_git(&repo_path, ["push", "-u", "origin", "main"]);
+
+        let problems = rename_remote(path_str(&repo_path), "origin", "fork").unwrap();
+        assert!(problems.is_empty());
+        assert_eq!(
+            run_git(&repo_path, ["rev-parse", "--abbrev-ref", "main@{upstream}"]).trim(),
+            "fork/main"
         );
+
+        set_remote_url(path_str(&repo_path), "fork", path_str(&second), true).unwrap();
+        let remotes = get_remotes(path_str(&repo_path)).unwrap();
+        assert_eq!(remotes.len(), 1);
+        assert_eq!(remotes[0].name, "fork");
+        assert_eq!(remotes[0].refs.fetch, path_str(&first));
+        assert_eq!(remotes[0].refs.push, path_str(&second));
+
+        set_remote_url(path_str(&repo_path), "fork", path_str(&second), false).unwrap();
+        assert_eq!(get_remotes(path_str(&repo_path)).unwrap()[0].refs.fetch, path_str(&second));
+
+        remove_remote(path_str(&repo_path), "fork").unwrap();
+        assert!(get_remotes(path_str(&repo_path)).unwrap().is_empty());
+        assert!(matches!(remove_remote(path_str(&repo_path), "fork"), Err(GitError::NoRemote)));
     }
 
-    fn get_head(repo_path: &Path) -> String {
-        let output = run_git(repo_path, ["rev-parse", "HEAD"]);
-        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    #[test]
+    fn fetch_from_unreachable_remote_is_not_an_auth_failure() {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        let missing = temp.path().join("missing.git");
+        add_remote(path_str(&repo_path), "origin", path_str(&missing)).unwrap();
+
+        match fetch(path_str(&repo_path), None) {
+            Err(GitError::Network(_)) => {}
+            other => panic!("Expected Network, got: {:?}", other),
+        }
     }
 
-    fn has_rebase_state(repo_path: &Path) -> bool {
-        let git_dir_output = run_git(repo_path, ["rev-parse", "--git-dir"]);
-        let git_dir = String::from_utf8(git_dir_output.stdout).unwrap();
-        let git_dir = repo_path.join(git_dir.trim());
-        git_dir.join("rebase-merge").exists() || git_dir.join("rebase-apply").exists()
+    #[test]
+    fn ship_sync_and_fetch_use_named_and_upstream_remotes() {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        let upstream = init_bare(&temp, "upstream.git");
+        let fork = init_bare(&temp, "fork.git");
+        add_remote(path_str(&repo_path), "upstream", path_str(&upstream)).unwrap();
+        add_remote(path_str(&repo_path), "fork", path_str(&fork)).unwrap();
+
+        // No origin and several remotes: a remote has to be chosen explicitly
+        assert!(matches!(ship(path_str(&repo_path)), Err(GitError::NoRemote)));
+
+        let options = ShipOptions {
+            remote: Some("fork".to_string()),
+            ..Default::default()
+        };
+        let result = ship_with_options(path_str(&repo_path), &options).unwrap();
+        assert_eq!(result.remote, "fork");
+        assert_eq!(
+            run_git(&repo_path, ["rev-parse", "--abbrev-ref", "main@{upstream}"]).trim(),
+            "fork/main"
+        );
+
+        // Shipping to another remote does not move the upstream
+        let options = ShipOptions {
+            remote: Some("upstream".to_string()),
+            ..Default::default()
+        };
+        ship_with_options(path_str(&repo_path), &options).unwrap();
+        assert_eq!(
+            run_git(&repo_path, ["rev-parse", "--abbrev-ref", "main@{upstream}"]).trim(),
+            "fork/main"
+        );
+
+        // Defaults follow the upstream remote
+        commit_file(&repo_path, "file.txt", "next\n", "next");
+        assert_eq!(ship(path_str(&repo_path)).unwrap().remote, "fork");
+
+        let other = temp.path().join("other");
+        run_git(temp.path(), ["clone", path_str(&fork), path_str(&other)]);
+        run_git(&other, ["config", "user.name", "Other"]);
+        run_git(&other, ["config", "user.email", "other@example.com"]);
+        commit_file(&other, "fork.txt", "fork\n", "fork change");
+        run_git(&other, ["push", "origin", "main"]);
+        let fork_head = run_git(&other, ["rev-parse", "HEAD"]).trim().to_string();
+
+        fetch(path_str(&repo_path), Some("fork")).unwrap();
+        assert_eq!(run_git(&repo_path, ["rev-parse", "fork/main"]).trim(), fork_head);
+
+        assert_eq!(sync(path_str(&repo_path)).unwrap().pulled, 1);
+        assert_eq!(run_git(&repo_path, ["rev-parse", "HEAD"]).trim(), fork_head);
+
+        let sync_options = SyncOptions {
+            remote: Some("missing".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(sync_with_options(path_str(&repo_path), &sync_options), Err(GitError::NoRemote)));
+        assert!(fetch(path_str(&repo_path), Some("missing")).is_err());
     }
+}
 
-    fn setup_repo_with_remote() -> (tempfile::TempDir, PathBuf, PathBuf) {
-        let temp = tempfile::tempdir().unwrap();
+#[cfg(test)]
+mod sync_tests {
+    use super::test_support::*;
+    use super::*;
+
+    /// A local repo and a second clone ("other") sharing a bare remote.
+    fn init_pair() -> (tempfile::TempDir, std::path::PathBuf, std::path::PathBuf) {
+        let (temp, repo_path) = init_test_repo();
         let remote_path = temp.path().join("remote.git");
-        let repo_path = temp.path().join("local");
+        run_git(temp.path(), ["init", "--bare", "-b", "main", path_str(&remote_path)]);
+        run_git(&repo_path, ["remote", "add", "origin", path_str(&remote_path)]);
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        run_git(&repo_path, ["push", "-u", "origin", "main"]);
 
-        let init_remote = Command::new("git")
-            .args(["init", "--bare", remote_path.to_str().unwrap()])
-            .output()
-            .unwrap();
-        assert!(
-            init_remote.status.success(),
-            "git init --bare failed: stdout={} stderr={}",
-            String::from_utf8_lossy(&init_remote.stdout),
-            String::from_utf8_lossy(&init_remote.stderr)
+        let other = temp.path().join("other");
+        run_git(temp.path(), ["clone", path_str(&remote_path), path_str(&other)]);
+        run_git(&other, ["config", "user.name", "Other"]);
+        run_git(&other, ["config", "user.email", "other@example.com"]);
+        (temp, repo_path, other)
+    }
+
+    #[test]
+    fn ff_only_sync_counts_commits_and_lists_files() {
+        let (_temp, repo_path, other) = init_pair();
+        commit_file(&other, "a.txt", "a\n", "add a");
+        commit_file(&other, "file.txt", "changed\n", "change file");
+        run_git(&other, ["push", "origin", "main"]);
+
+        let result = sync(path_str(&repo_path)).unwrap();
+        assert_eq!(result.pulled, 2);
+        assert_eq!(result.pushed, 0);
+        assert!(!result.conflicts);
+        let files: Vec<(&str, &str)> = result
+            .changed_files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status.as_str()))
+            .collect();
+        assert_eq!(files, vec![("a.txt", "added"), ("file.txt", "modified")]);
+
+        assert_eq!(sync(path_str(&repo_path)).unwrap().pulled, 0);
+    }
+
+    #[test]
+    fn diverged_ff_only_fails_and_project_rebase_strategy_pushes() {
+        let (_temp, repo_path, other) = init_pair();
+        commit_file(&other, "remote.txt", "remote\n", "remote work");
+        run_git(&other, ["push", "origin", "main"]);
+        commit_file(&repo_path, "local.txt", "local\n", "local work");
+
+        match sync(path_str(&repo_path)) {
+            Err(GitError::Diverged(_)) => {}
+            other => panic!("Expected Diverged, got: {:?}", other),
+        }
+
+        set_sync_strategy(path_str(&repo_path), SyncStrategy::Rebase).unwrap();
+        assert_eq!(get_sync_strategy(path_str(&repo_path)).unwrap(), SyncStrategy::Rebase);
+
+        let options = SyncOptions {
+            push: true,
+            ..Default::default()
+        };
+        let result = sync_with_options(path_str(&repo_path), &options).unwrap();
+        assert_eq!(result.pulled, 1);
+        assert_eq!(result.pushed, 1);
+        assert_eq!(run_git(&repo_path, ["rev-list", "--count", "HEAD"]).trim(), "3");
+        assert_eq!(
+            run_git(&repo_path, ["rev-parse", "HEAD"]),
+            run_git(&repo_path, ["rev-parse", "origin/main"])
         );
+    }
 
-        fs::create_dir_all(&repo_path).unwrap();
-        run_git(&repo_path, ["init", "-b", "main"]);
-        run_git(&repo_path, ["config", "user.name", "Test User"]);
-        run_git(&repo_path, ["config", "user.email", "test@example.com"]);
+    #[test]
+    fn merge_strategy_reports_conflicts() {
+        let (_temp, repo_path, other) = init_pair();
+        commit_file(&other, "file.txt", "theirs\n", "remote edit");
+        run_git(&other, ["push", "origin", "main"]);
+        commit_file(&repo_path, "file.txt", "ours\n", "local edit");
+
+        let options = SyncOptions {
+            strategy: Some(SyncStrategy::Merge),
+            push: true,
+            ..Default::default()
+        };
+        let result = sync_with_options(path_str(&repo_path), &options).unwrap();
+        assert!(result.conflicts);
+        assert_eq!(result.pulled, 0);
+        assert_eq!(result.pushed, 0);
+        assert_eq!(list_conflicts(path_str(&repo_path)).unwrap().operation, RepoOperation::Merge);
+
+        // A second sync refuses to pull on top of the unresolved merge
+        match sync_with_options(path_str(&repo_path), &options) {
+            Err(GitError::Git2(message)) => assert!(message.contains("in progress")),
+            other => panic!("Expected sync to refuse, got: {:?}", other),
+        }
+        assert_eq!(list_conflicts(path_str(&repo_path)).unwrap().conflicts.len(), 1);
+    }
+
+    #[test]
+    fn classify_pull_error_variants() {
+        assert!(matches!(
+            classify_pull_error("fatal: Authentication failed for 'https://github.com/x'"),
+            GitError::AuthFailed(_)
+        ));
+        assert!(matches!(
+            classify_pull_error("fatal: Not possible to fast-forward, aborting."),
+            GitError::Diverged(_)
+        ));
+        assert!(matches!(
+            classify_pull_error("fatal: unable to access 'https://github.com/x': Could not resolve host: github.com"),
+            GitError::Network(_)
+        ));
+        assert!(matches!(classify_pull_error("fatal: something else"), GitError::Git2(_)));
+    }
+}
+
+#[cfg(test)]
+mod blame_tests {
+    use super::test_support::*;
+    use super::*;
+
+    #[test]
+    fn blame_attributes_lines_and_follows_renames() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "old.txt", "first\nsecond\nthird\n", "add file");
+        let first_sha = run_git(&repo_path, ["rev-parse", "HEAD"]).trim().to_string();
+        run_git(&repo_path, ["mv", "old.txt", "new.txt"]);
+        run_git(&repo_path, ["commit", "-m", "rename file"]);
+        commit_file(&repo_path, "new.txt", "zeroth\nfirst\nsecond\nTHIRD\n", "edit file");
+        let edit_sha = run_git(&repo_path, ["rev-parse", "HEAD"]).trim().to_string();
+
+        let lines = blame_file(path_str(&repo_path), "new.txt", Some("HEAD"), None).unwrap();
+        let shas: Vec<&str> = lines.iter().map(|l| l.commit.as_ref().unwrap().sha.as_str()).collect();
+        assert_eq!(shas, vec![edit_sha.as_str(), &first_sha, &first_sha, &edit_sha]);
+
+        assert_eq!(lines[1].content, "first");
+        assert_eq!(lines[1].line_number, 2);
+        assert_eq!(lines[1].original_line_number, 1);
+        assert_eq!(lines[1].original_path.as_deref(), Some("old.txt"));
+        assert_eq!(lines[1].summary, "add file");
+        assert_eq!(lines[3].summary, "edit file");
+
+        // Blaming an older revision only sees that version of the file
+        let older = blame_file(path_str(&repo_path), "new.txt", Some("HEAD~1"), None).unwrap();
+        assert_eq!(older.len(), 3);
+    }
+
+    #[test]
+    fn blame_marks_unsaved_lines() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "one\ntwo\n", "add file");
+        write_file(&repo_path, "file.txt", "one\nchanged\ntwo\n");
+
+        let lines = blame_file(path_str(&repo_path), "file.txt", None, None).unwrap();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].commit.is_some());
+        assert!(lines[1].commit.is_none());
+        assert_eq!(lines[1].summary, "Not yet saved");
+        assert_eq!(lines[2].commit.as_ref().unwrap().message.trim(), "add file");
+        assert_eq!(lines[2].original_line_number, 2);
+
+        // An editor buffer takes precedence over the file on disk
+        let buffer = blame_file(path_str(&repo_path), "file.txt", None, Some("one\ntwo\nthree\n")).unwrap();
+        assert!(buffer[1].commit.is_some());
+        assert!(buffer[2].commit.is_none());
+
+        write_file(&repo_path, "untracked.txt", "brand new\n");
+        let untracked = blame_file(path_str(&repo_path), "untracked.txt", None, None).unwrap();
+        assert!(untracked.iter().all(|l| l.commit.is_none()));
+    }
+}
+
+#[cfg(test)]
+mod file_history_tests {
+    use super::test_support::*;
+    use super::*;
+
+    #[test]
+    fn history_follows_renames_with_line_stats() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "old.txt", "one\ntwo\nthree\nfour\nfive\n", "create");
+        commit_file(&repo_path, "other.txt", "unrelated\n", "unrelated");
+        commit_file(&repo_path, "old.txt", "one\ntwo\nTHREE\nfour\nfive\n", "edit before rename");
+        run_git(&repo_path, ["mv", "old.txt", "new.txt"]);
+        run_git(&repo_path, ["commit", "-m", "rename"]);
+        commit_file(&repo_path, "new.txt", "one\ntwo\nTHREE\nfour\nfive\nsix\nseven\n", "extend");
+
+        let history = get_file_history(path_str(&repo_path), "new.txt", None, None).unwrap();
+        let summary: Vec<(&str, &str, &str, usize, usize)> = history
+            .iter()
+            .map(|e| {
+                (
+                    e.commit.message.trim(),
+                    e.path.as_str(),
+                    e.status.as_str(),
+                    e.additions,
+                    e.deletions,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("extend", "new.txt", "modified", 2, 0),
+                ("rename", "new.txt", "renamed", 0, 0),
+                ("edit before rename", "old.txt", "modified", 1, 1),
+                ("create", "old.txt", "added", 5, 0),
+            ]
+        );
+        assert_eq!(history[1].old_path.as_deref(), Some("old.txt"));
+    }
+
+    #[test]
+    fn history_is_paginated() {
+        let (_temp, repo_path) = init_test_repo();
+        for i in 0..5 {
+            commit_file(&repo_path, "file.txt", &format!("{}\n", i), &format!("save {}", i));
+        }
+
+        let page = get_file_history(path_str(&repo_path), "file.txt", Some(2), Some(1)).unwrap();
+        let messages: Vec<&str> = page.iter().map(|e| e.commit.message.trim()).collect();
+        assert_eq!(messages, vec!["save 3", "save 2"]);
+
+        let rest = get_file_history(path_str(&repo_path), "file.txt", Some(10), Some(4)).unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].status, "added");
+    }
+}
+
+#[cfg(test)]
+mod show_commit_tests {
+    use super::test_support::*;
+    use super::*;
+
+    #[test]
+    fn show_commit_lists_files_stats_and_hunks() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "keep.txt", "one\ntwo\nthree\n", "base");
+        commit_file(&repo_path, "gone.txt", "bye\n", "add gone");
+        write_file(&repo_path, "keep.txt", "one\nTWO\nthree\nfour\n");
+        write_file(&repo_path, "new.txt", "hello\n");
+        std::fs::remove_file(repo_path.join("gone.txt")).unwrap();
+        run_git(&repo_path, ["add", "-A"]);
         run_git(
             &repo_path,
-            ["remote", "add", "origin", remote_path.to_str().unwrap()],
+            ["-c", "user.name=Committer", "commit", "-m", "Change things\n\nWith a body"],
         );
 
-        (temp, repo_path, remote_path)
+        let detail = show_commit(path_str(&repo_path), "HEAD").unwrap();
+        assert_eq!(detail.commit.message, "Change things\n\nWith a body\n");
+        assert_eq!(detail.committer, "Committer");
+        assert_eq!(detail.commit.parent_shas.len(), 1);
+
+        let files: Vec<(&str, &str, usize, usize)> = detail
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status.as_str(), f.additions, f.deletions))
+            .collect();
+        assert_eq!(
+            files,
+            vec![("gone.txt", "deleted", 0, 1), ("keep.txt", "modified", 2, 1), ("new.txt", "added", 1, 0)]
+        );
+        assert_eq!(detail.diffs.len(), 3);
+        assert_eq!(detail.diffs[1].path, "keep.txt");
+        assert!(detail.diffs[1].hunks[0].lines.iter().any(|l| l.line_type == "add" && l.content == "TWO\n"));
     }
 
     #[test]
-    fn classify_push_error_auth_failed() {
-        let err = classify_push_error("fatal: Authentication failed for 'https://github.com/...'", false);
-        match err {
-            GitError::AuthFailed(msg) => {
-                assert!(msg.contains("Authentication failed"));
-            }
-            other => panic!("Expected AuthFailed, got: {:?}", other),
-        }
+    fn merge_commit_diffs_against_first_parent() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        run_git(&repo_path, ["checkout", "-b", "feature"]);
+        commit_file(&repo_path, "feature.txt", "feature\n", "feature");
+        run_git(&repo_path, ["checkout", "main"]);
+        commit_file(&repo_path, "main.txt", "main\n", "main");
+        run_git(&repo_path, ["merge", "--no-ff", "--no-edit", "feature"]);
+
+        let detail = show_commit(path_str(&repo_path), "HEAD").unwrap();
+        assert_eq!(detail.commit.parent_shas.len(), 2);
+        let paths: Vec<&str> = detail.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["feature.txt"]);
+
+        let root = run_git(&repo_path, ["rev-list", "--max-parents=0", "HEAD"]).trim().to_string();
+        let initial = show_commit(path_str(&repo_path), &root).unwrap();
+        assert_eq!(initial.files[0].status, "added");
     }
+}
+
+#[cfg(test)]
+mod compare_tests {
+    use super::test_support::*;
+    use super::*;
 
     #[test]
-    fn classify_push_error_permission_denied() {
-        let err = classify_push_error("ERROR: Permission denied (publickey).\nfatal: Could not read from remote.", false);
-        match err {
-            GitError::AuthFailed(msg) => {
-                assert!(msg.contains("Permission denied"));
-            }
-            other => panic!("Expected AuthFailed, got: {:?}", other),
-        }
+    fn compare_reports_merge_base_unique_commits_and_files() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        let base_sha = run_git(&repo_path, ["rev-parse", "HEAD"]).trim().to_string();
+        run_git(&repo_path, ["checkout", "-b", "feature"]);
+        commit_file(&repo_path, "feature.txt", "one\ntwo\n", "feature one");
+        commit_file(&repo_path, "file.txt", "base\nfeature\n", "feature two");
+        run_git(&repo_path, ["checkout", "main"]);
+        commit_file(&repo_path, "main.txt", "main\n", "main only");
+        run_git(&repo_path, ["tag", "v1", "main"]);
+
+        let result = compare(path_str(&repo_path), "v1", "feature", false).unwrap();
+        assert_eq!(result.merge_base.as_deref(), Some(base_sha.as_str()));
+        let head_messages: Vec<&str> = result.head_commits.iter().map(|c| c.message.trim()).collect();
+        assert_eq!(head_messages, vec!["feature two", "feature one"]);
+        let base_messages: Vec<&str> = result.base_commits.iter().map(|c| c.message.trim()).collect();
+        assert_eq!(base_messages, vec!["main only"]);
+
+        // main.txt only changed on the base side, so it is not part of the diff
+        let files: Vec<(&str, &str, usize, usize)> = result
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status.as_str(), f.additions, f.deletions))
+            .collect();
+        assert_eq!(files, vec![("feature.txt", "added", 2, 0), ("file.txt", "modified", 1, 0)]);
+        assert!(result.diffs.is_none());
+
+        let with_hunks = compare(path_str(&repo_path), "main", &result.head_sha, true).unwrap();
+        let diffs = with_hunks.diffs.unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[1].hunks[0].lines.last().unwrap().content, "feature\n");
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use super::test_support::*;
+    use super::*;
+
+    fn messages(page: &HistoryPage) -> Vec<&str> {
+        page.commits.iter().map(|c| c.message.trim()).collect()
     }
 
     #[test]
-    fn classify_push_error_rejected_non_fast_forward() {
-        let err = classify_push_error("error: failed to push some refs to 'origin'\nhint: Updates were rejected because the remote contains work", false);
-        match err {
-            GitError::PushRejected(msg) => {
-                assert!(msg.contains("failed to push"));
-            }
-            other => panic!("Expected PushRejected, got: {:?}", other),
+    fn query_history_pages_with_cursor_until_exhausted() {
+        let (_temp, repo_path) = init_test_repo();
+        for i in 1..=5 {
+            commit_file(&repo_path, "file.txt", &format!("{}\n", i), &format!("commit {}", i));
         }
+        let mut query = HistoryQuery { limit: Some(2), ..Default::default() };
+
+        let first = query_history(path_str(&repo_path), &query).unwrap();
+        assert_eq!(messages(&first), vec!["commit 5", "commit 4"]);
+
+        // New commits on top must not shift the pages already handed out
+        commit_file(&repo_path, "file.txt", "6\n", "commit 6");
+        query.cursor = first.next_cursor.clone();
+        let second = query_history(path_str(&repo_path), &query).unwrap();
+        assert_eq!(messages(&second), vec!["commit 3", "commit 2"]);
+
+        query.cursor = second.next_cursor.clone();
+        let last = query_history(path_str(&repo_path), &query).unwrap();
+        assert_eq!(messages(&last), vec!["commit 1"]);
+        assert!(last.next_cursor.is_none());
+
+        query.cursor = Some("garbage".to_string());
+        assert!(query_history(path_str(&repo_path), &query).is_err());
+    }
+
+    #[test]
+    fn query_history_respects_scope_and_skip() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        run_git(&repo_path, ["checkout", "-b", "feature"]);
+        commit_file(&repo_path, "feature.txt", "f\n", "feature work");
+        run_git(&repo_path, ["checkout", "main"]);
+        commit_file(&repo_path, "main.txt", "m\n", "main work");
+
+        let head = query_history(path_str(&repo_path), &HistoryQuery::default()).unwrap();
+        assert_eq!(messages(&head), vec!["main work", "base"]);
+        assert_eq!(head.commits[0].refs.as_ref().unwrap()[0], "HEAD -> main");
+
+        let feature = HistoryQuery {
+            scope: HistoryScope::Ref { name: "feature".to_string() },
+            ..Default::default()
+        };
+        let feature_page = query_history(path_str(&repo_path), &feature).unwrap();
+        assert_eq!(messages(&feature_page), vec!["feature work", "base"]);
+
+        let all = HistoryQuery { scope: HistoryScope::AllBranches, skip: Some(1), ..Default::default() };
+        let all_page = query_history(path_str(&repo_path), &all).unwrap();
+        assert_eq!(all_page.commits.len(), 2);
+        assert_eq!(all_page.commits[1].message.trim(), "base");
+    }
+
+    #[test]
+    fn query_history_filters_by_author_message_date_and_path() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "src/lib.rs", "one\n", "Add lib");
+        // Date filters use the committer date, like `git log --since`
+        let output = std::process::Command::new("git")
+            .args(["commit", "--allow-empty", "-m", "Fix typo"])
+            .env("GIT_AUTHOR_NAME", "Other Person")
+            .env("GIT_AUTHOR_EMAIL", "other@example.com")
+            .env("GIT_COMMITTER_DATE", "2001-01-01T00:00:00Z")
+            .current_dir(&repo_path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        commit_file(&repo_path, "README.md", "readme\n", "Docs");
+        commit_file(&repo_path, "src/lib.rs", "two\n", "Fix lib");
+
+        let by_author = HistoryQuery { author: Some("OTHER@".to_string()), ..Default::default() };
+        let page = query_history(path_str(&repo_path), &by_author).unwrap();
+        assert_eq!(messages(&page), vec!["Fix typo"]);
+
+        let by_message = HistoryQuery { message: Some("fix".to_string()), limit: Some(1), ..Default::default() };
+        let page = query_history(path_str(&repo_path), &by_message).unwrap();
+        assert_eq!(messages(&page), vec!["Fix lib"]);
+        assert!(page.next_cursor.is_some());
+
+        let by_date = HistoryQuery { until: Some(1_000_000_000), ..Default::default() };
+        let page = query_history(path_str(&repo_path), &by_date).unwrap();
+        assert_eq!(messages(&page), vec!["Fix typo"]);
+
+        let by_path = HistoryQuery { path: Some("src".to_string()), ..Default::default() };
+        let page = query_history(path_str(&repo_path), &by_path).unwrap();
+        assert_eq!(messages(&page), vec!["Fix lib", "Add lib"]);
+        assert!(page.next_cursor.is_none());
+    }
+}
+
+#[cfg(test)]
+mod graph_tests {
+    use super::test_support::*;
+    use super::*;
+
+    fn setup_merge() -> (tempfile::TempDir, std::path::PathBuf) {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        run_git(&repo_path, ["checkout", "-b", "feature"]);
+        commit_file(&repo_path, "feature.txt", "f\n", "feature work");
+        run_git(&repo_path, ["checkout", "main"]);
+        commit_file(&repo_path, "main.txt", "m\n", "main work");
+        run_git(&repo_path, ["merge", "--no-ff", "feature", "-m", "merge feature"]);
+        commit_file(&repo_path, "main.txt", "m2\n", "after merge");
+        (temp, repo_path)
+    }
+
+    fn row<'a>(commits: &'a [Commit], message: &str) -> &'a GraphRow {
+        commits
+            .iter()
+            .find(|c| c.message.trim() == message)
+            .and_then(|c| c.graph.as_ref())
+            .unwrap()
+    }
+
+    #[test]
+    fn get_log_lays_out_branch_and_merge_lanes() {
+        let (_temp, repo_path) = setup_merge();
+        let commits = get_log(path_str(&repo_path), None, true).unwrap();
+        assert_eq!(commits.len(), 5);
+
+        let tip = row(&commits, "after merge");
+        assert_eq!((tip.column, tip.color, tip.has_incoming), (0, 0, false));
+
+        let merge = row(&commits, "merge feature");
+        assert!(merge.has_incoming);
+        assert_eq!(merge.parents.len(), 2);
+        assert_eq!((merge.parents[0].from_column, merge.parents[0].to_column), (0, 0));
+        assert_eq!(merge.parents[1].to_column, 1);
+        assert_eq!(merge.parents[1].color, 1);
+
+        let feature = row(&commits, "feature work");
+        assert_eq!((feature.column, feature.color), (1, 1));
+        let main = row(&commits, "main work");
+        assert_eq!(main.column, 0);
+
+        // The feature lane ends where it forked off
+        let base = row(&commits, "base");
+        assert_eq!(base.column, 0);
+        assert_eq!(base.merging, vec![GraphLane { column: 1, color: 1 }]);
+        assert!(base.parents.is_empty());
+
+        assert!(get_log(path_str(&repo_path), None, false).unwrap()[0].graph.is_none());
     }
 
     #[test]
-    fn classify_push_error_non_fast_forward() {
-        let err = classify_push_error("To github.com:user/repo.git\n ! [rejected]        main -> main (non-fast-forward)", false);
-        match err {
-            GitError::PushRejected(msg) => {
-                assert!(msg.contains("non-fast-forward"));
+    fn graph_layout_is_identical_across_pages() {
+        let (_temp, repo_path) = setup_merge();
+        let full = HistoryQuery { scope: HistoryScope::AllBranches, graph: true, ..Default::default() };
+        let expected: Vec<GraphRow> = query_history(path_str(&repo_path), &full)
+            .unwrap()
+            .commits
+            .into_iter()
+            .map(|c| c.graph.unwrap())
+            .collect();
+
+        let mut query = HistoryQuery { limit: Some(2), ..full };
+        let mut rows = Vec::new();
+        loop {
+            let page = query_history(path_str(&repo_path), &query).unwrap();
+            rows.extend(page.commits.into_iter().map(|c| c.graph.unwrap()));
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
             }
-            other => panic!("Expected PushRejected, got: {:?}", other),
         }
+        assert_eq!(rows, expected);
+
+        // Skipping still advances the layout through the skipped commits
+        let skipped = HistoryQuery { skip: Some(3), cursor: None, ..query };
+        let page = query_history(path_str(&repo_path), &skipped).unwrap();
+        assert_eq!(page.commits[0].graph.as_ref().unwrap(), &expected[3]);
     }
+}
 
-    #[test]
-    fn classify_push_error_after_rebase() {
-        let err = classify_push_error("fatal: unable to access 'https://github.com/...'", true);
-        match err {
-            GitError::PushRejected(msg) => {
-                assert!(msg.contains("Rebase succeeded but push failed"));
-            }
-            other => panic!("Expected PushRejected with rebase context, got: {:?}", other),
-        }
+#[cfg(test)]
+mod pick_tests {
+    use super::test_support::*;
+    use super::*;
+
+    fn rev(repo_path: &Path, rev: &str) -> String {
+        run_git(repo_path, ["rev-parse", rev]).trim().to_string()
     }
 
     #[test]
-    fn classify_push_error_generic() {
-        let err = classify_push_error("fatal: some unknown error", false);
-        match err {
-            GitError::PushRejected(msg) => {
-                assert!(msg.contains("some unknown error"));
-                // Verify it's NOT AuthFailed
-                assert!(!msg.contains("Authentication"));
-            }
-            other => panic!("Expected PushRejected, got: {:?}", other),
-        }
+    fn cherry_pick_applies_commits_from_another_branch() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        run_git(&repo_path, ["checkout", "-b", "feature"]);
+        commit_file(&repo_path, "fix.txt", "fix\n", "Fix crash");
+        commit_file(&repo_path, "other.txt", "other\n", "Other work");
+        let fix = rev(&repo_path, "HEAD~1");
+        run_git(&repo_path, ["checkout", "main"]);
+
+        let result = cherry_pick(path_str(&repo_path), std::slice::from_ref(&fix), &PickOptions::default()).unwrap();
+        assert_eq!(result.commits.len(), 1);
+        assert_eq!(result.head_sha, rev(&repo_path, "HEAD"));
+        let message = &result.commits[0].message;
+        assert!(message.starts_with("Fix crash"));
+        assert!(message.contains(&format!("(cherry picked from commit {})", fix)));
+        assert!(repo_path.join("fix.txt").exists());
+        assert!(!repo_path.join("other.txt").exists());
     }
 
     #[test]
-    fn classify_rebase_error_conflict() {
-        let err = classify_rebase_error("CONFLICT (content): Merge conflict in file.txt\nerror: could not apply abc1234... Fix");
-        match err {
-            GitError::RebaseConflict(msg) => {
-                assert!(msg.contains("conflict"));
-            }
-            other => panic!("Expected RebaseConflict, got: {:?}", other),
+    fn revert_adds_trailer_and_requires_mainline_for_merges() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        run_git(&repo_path, ["checkout", "-b", "feature"]);
+        commit_file(&repo_path, "feature.txt", "f\n", "feature work");
+        run_git(&repo_path, ["checkout", "main"]);
+        commit_file(&repo_path, "main.txt", "m\n", "main work");
+        run_git(&repo_path, ["merge", "--no-ff", "feature", "-m", "merge feature"]);
+        let merge = rev(&repo_path, "HEAD");
+
+        match revert(path_str(&repo_path), std::slice::from_ref(&merge), &PickOptions::default()) {
+            Err(GitError::Git2(message)) => assert!(message.contains("merge commit")),
+            other => panic!("Expected mainline error, got: {:?}", other),
         }
-    }
 
-    #[test]
-    fn classify_rebase_error_could_not_apply() {
-        let err = classify_rebase_error("error: could not apply 123abc4... some commit message");
-        match err {
-            GitError::RebaseConflict(msg) => {
-                assert!(msg.contains("conflict"));
-            }
-            other => panic!("Expected RebaseConflict, got: {:?}", other),
+        let options = PickOptions { mainline: Some(1) };
+        let mixed = vec![merge.clone(), rev(&repo_path, "HEAD~1")];
+        match revert(path_str(&repo_path), &mixed, &options) {
+            Err(GitError::Git2(message)) => assert!(message.contains("not a merge commit")),
+            other => panic!("Expected mixed selection error, got: {:?}", other),
         }
+        assert_eq!(rev(&repo_path, "HEAD"), merge);
+
+        let result = revert(path_str(&repo_path), std::slice::from_ref(&merge), &options).unwrap();
+        assert!(result.commits[0].message.trim_end().ends_with(&format!("Reverts: {}", merge)));
+        assert!(!repo_path.join("feature.txt").exists());
+        assert!(repo_path.join("main.txt").exists());
     }
 
     #[test]
-    fn classify_rebase_error_auth() {
-        let err = classify_rebase_error("fatal: Authentication failed for 'https://github.com/...'");
-        match err {
-            GitError::AuthFailed(msg) => {
-                assert!(msg.contains("Authentication"));
+    fn conflicting_revert_stops_resumably_and_continues_with_trailers() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "one\n", "one");
+        commit_file(&repo_path, "file.txt", "two\n", "two");
+        let two = rev(&repo_path, "HEAD");
+        commit_file(&repo_path, "file.txt", "three\n", "three");
+        commit_file(&repo_path, "extra.txt", "extra\n", "extra");
+        let extra = rev(&repo_path, "HEAD");
+
+        let shas = vec![two.clone(), extra.clone()];
+        match revert(path_str(&repo_path), &shas, &PickOptions::default()) {
+            Err(GitError::RevertConflict { sha, conflicts }) => {
+                assert_eq!(sha, two);
+                assert_eq!(conflicts, vec!["file.txt".to_string()]);
             }
-            other => panic!("Expected AuthFailed, got: {:?}", other),
+            other => panic!("Expected revert conflict, got: {:?}", other),
         }
+        assert_eq!(list_conflicts(path_str(&repo_path)).unwrap().operation, RepoOperation::Revert);
+
+        resolve_conflict(path_str(&repo_path), "file.txt", Some("one\n".to_string())).unwrap();
+        let state = continue_operation(path_str(&repo_path)).unwrap();
+        assert_eq!(state.operation, RepoOperation::None);
+
+        let log = run_git(&repo_path, ["log", "-2", "--format=%B"]);
+        assert!(log.contains(&format!("Reverts: {}", two)));
+        assert!(log.contains(&format!("Reverts: {}", extra)));
+        assert!(!repo_path.join("extra.txt").exists());
     }
+}
+
+#[cfg(test)]
+mod worktree_tests {
+    use super::test_support::*;
+    use super::*;
 
     #[test]
-    fn classify_rebase_error_generic() {
-        let err = classify_rebase_error("fatal: unable to access remote");
-        match err {
-            GitError::PushRejected(msg) => {
-                assert!(msg.contains("Pull --rebase failed"));
-            }
-            other => panic!("Expected PushRejected, got: {:?}", other),
-        }
+    fn added_worktree_works_as_a_project() {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        let remote = temp.path().join("remote.git");
+        run_git(temp.path(), ["init", "--bare", "-b", "main", path_str(&remote)]);
+        run_git(&repo_path, ["remote", "add", "origin", path_str(&remote)]);
+        run_git(&repo_path, ["push", "-u", "origin", "main"]);
+        commit_file(&repo_path, "file.txt", "newer\n", "newer");
+
+        let tree_path = temp.path().join("agent-1");
+        let added = add_worktree(path_str(&repo_path), path_str(&tree_path), "agent-1", Some("origin/main")).unwrap();
+        assert_eq!(added.branch.as_deref(), Some("agent-1"));
+        assert!(!added.is_main);
+        assert_eq!(std::fs::read_to_string(tree_path.join("file.txt")).unwrap(), "base\n");
+        assert!(add_worktree(path_str(&repo_path), path_str(&tree_path), "agent-1", None).is_err());
+
+        let worktrees = list_worktrees(path_str(&tree_path)).unwrap();
+        assert_eq!(worktrees.len(), 2);
+        assert!(worktrees[0].is_main);
+        assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+        assert_eq!(
+            main_worktree_path(path_str(&tree_path)).map(|p| std::fs::canonicalize(p).unwrap()),
+            Some(std::fs::canonicalize(&repo_path).unwrap())
+        );
+        assert!(main_worktree_path(path_str(&repo_path)).is_none());
+
+        write_file(&tree_path, "agent.txt", "work\n");
+        let status = get_status(path_str(&tree_path)).unwrap();
+        assert_eq!(status.branch, "agent-1");
+        assert_eq!(status.untracked_files, vec!["agent.txt".to_string()]);
+
+        save(path_str(&tree_path), Some("Agent work".to_string())).unwrap();
+        ship(path_str(&tree_path)).unwrap();
+        assert_eq!(
+            run_git(&remote, ["log", "-1", "--format=%s", "agent-1"]).trim(),
+            "Agent work"
+        );
+        // The main working tree is untouched
+        assert_eq!(run_git(&repo_path, ["status", "--porcelain"]).trim(), "");
     }
 
     #[test]
-    fn ship_result_serializes_with_rebased_field() {
-        let result = ShipResult {
-            pushed: true,
-            commits_pushed: 3,
-            remote: "origin".to_string(),
-            branch: "main".to_string(),
-            rebased: true,
-        };
-        let json = serde_json::to_string(&result).unwrap();
-        assert!(json.contains("\"rebased\":true"));
-        assert!(json.contains("\"commitsPushed\":3"));
+    fn relative_worktree_path_is_resolved_against_the_repository() {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+
+        let added = add_worktree(path_str(&repo_path), "../sibling", "sibling", None).unwrap();
+        assert_eq!(added.branch.as_deref(), Some("sibling"));
+        assert_eq!(
+            std::fs::canonicalize(&added.path).unwrap(),
+            std::fs::canonicalize(temp.path().join("sibling")).unwrap()
+        );
     }
 
     #[test]
-    fn ship_result_no_rebase() {
-        let result = ShipResult {
-            pushed: true,
-            commits_pushed: 1,
-            remote: "origin".to_string(),
-            branch: "main".to_string(),
-            rebased: false,
-        };
-        let json = serde_json::to_string(&result).unwrap();
-        assert!(json.contains("\"rebased\":false"));
+    fn lock_prune_and_remove_worktrees() {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        let kept = temp.path().join("kept");
+        let gone = temp.path().join("gone");
+        add_worktree(path_str(&repo_path), path_str(&kept), "kept", None).unwrap();
+        add_worktree(path_str(&repo_path), path_str(&gone), "gone", None).unwrap();
+
+        lock_worktree(path_str(&repo_path), path_str(&kept), Some("agent running")).unwrap();
+        let locked = list_worktrees(path_str(&repo_path)).unwrap();
+        let kept_entry = locked.iter().find(|w| w.branch.as_deref() == Some("kept")).unwrap();
+        assert!(kept_entry.is_locked);
+        assert_eq!(kept_entry.lock_reason.as_deref(), Some("agent running"));
+        assert!(remove_worktree(path_str(&repo_path), path_str(&kept), false).is_err());
+
+        std::fs::remove_dir_all(&gone).unwrap();
+        let pruned = prune_worktrees(path_str(&repo_path)).unwrap();
+        assert_eq!(pruned.len(), 1);
+        assert!(pruned[0].ends_with("gone"));
+
+        unlock_worktree(path_str(&repo_path), path_str(&kept)).unwrap();
+        write_file(&kept, "dirty.txt", "dirty\n");
+        assert!(remove_worktree(path_str(&repo_path), path_str(&kept), false).is_err());
+        remove_worktree(path_str(&repo_path), path_str(&kept), true).unwrap();
+        assert!(!kept.exists());
+        assert_eq!(list_worktrees(path_str(&repo_path)).unwrap().len(), 1);
+        // Branches outlive their worktrees
+        assert!(run_git(&repo_path, ["branch", "--list", "kept"]).contains("kept"));
     }
+}
 
-    #[test]
-    fn git_error_rebase_conflict_display() {
-        let err = GitError::RebaseConflict("test conflict".to_string());
-        assert_eq!(format!("{}", err), "Rebase conflict: test conflict");
+#[cfg(test)]
+mod submodule_tests {
+    use super::test_support::*;
+    use super::*;
+
+    /// Local submodule URLs are blocked for the clones git runs internally, and
+    /// those clones ignore repository config, so allow them for every git call.
+    fn allow_file_protocol() {
+        static ALLOW: std::sync::Once = std::sync::Once::new();
+        ALLOW.call_once(|| {
+            std::env::set_var("GIT_CONFIG_COUNT", "1");
+            std::env::set_var("GIT_CONFIG_KEY_0", "protocol.file.allow");
+            std::env::set_var("GIT_CONFIG_VALUE_0", "always");
+        });
     }
 
-    #[test]
-    fn git_error_push_rejected_display() {
-        let err = GitError::PushRejected("test rejection".to_string());
-        assert_eq!(format!("{}", err), "Push rejected: test rejection");
+    fn setup_superproject(temp: &tempfile::TempDir) -> (std::path::PathBuf, std::path::PathBuf) {
+        allow_file_protocol();
+        let lib = temp.path().join("lib");
+        std::fs::create_dir_all(&lib).unwrap();
+        run_git(&lib, ["init", "-b", "main"]);
+        run_git(&lib, ["config", "user.name", "Test User"]);
+        run_git(&lib, ["config", "user.email", "test@example.com"]);
+        commit_file(&lib, "lib.txt", "v1\n", "lib v1");
+
+        let app = temp.path().join("app");
+        std::fs::create_dir_all(&app).unwrap();
+        run_git(&app, ["init", "-b", "main"]);
+        run_git(&app, ["config", "user.name", "Test User"]);
+        run_git(&app, ["config", "user.email", "test@example.com"]);
+        commit_file(&app, "app.txt", "app\n", "app");
+        run_git(&app, ["submodule", "add", path_str(&lib), "vendor/lib"]);
+        run_git(&app, ["commit", "-m", "Add lib"]);
+        (lib, app)
     }
 
     #[test]
-    fn git_error_serializes_correctly() {
-        let rebase_err = GitError::RebaseConflict("conflict details".to_string());
-        let json = serde_json::to_string(&rebase_err).unwrap();
-        assert!(json.contains("RebaseConflict"));
-
-        let push_err = GitError::PushRejected("push details".to_string());
-        let json = serde_json::to_string(&push_err).unwrap();
-        assert!(json.contains("PushRejected"));
+    fn status_reports_recorded_checked_out_and_dirty_state() {
+        let temp = tempfile::tempdir().unwrap();
+        let (lib, app) = setup_superproject(&temp);
+        let recorded = run_git(&lib, ["rev-parse", "HEAD"]).trim().to_string();
+
+        let status = get_status(path_str(&app)).unwrap();
+        assert_eq!(status.submodules.len(), 1);
+        let submodule = &status.submodules[0];
+        assert_eq!(submodule.path, "vendor/lib");
+        assert_eq!(submodule.recorded_sha.as_deref(), Some(recorded.as_str()));
+        assert_eq!(submodule.checked_out_sha.as_deref(), Some(recorded.as_str()));
+        assert!(submodule.is_initialized);
+        assert!(!submodule.is_dirty);
+
+        let checkout = app.join("vendor/lib");
+        write_file(&checkout, "lib.txt", "local edit\n");
+        assert!(list_submodules(path_str(&app)).unwrap()[0].is_dirty);
+
+        run_git(&checkout, ["-c", "user.name=T", "-c", "user.email=t@e", "commit", "-am", "moved"]);
+        let moved = list_submodules(path_str(&app)).unwrap().remove(0);
+        assert!(!moved.is_dirty);
+        assert!(get_status(path_str(&app)).unwrap().changed_files.is_empty());
+        assert_eq!(moved.recorded_sha.as_deref(), Some(recorded.as_str()));
+        assert_ne!(moved.checked_out_sha, moved.recorded_sha);
     }
 
     #[test]
-    fn ship_no_remote_returns_error() {
-        // Create a temp repo without any remote
-        let tmp = tempfile::tempdir().unwrap();
-        let repo = Repository::init(tmp.path()).unwrap();
-
-        // Create an initial commit so HEAD exists
-        let sig = Signature::now("Test", "test@test.com").unwrap();
-        let tree_id = {
-            let mut index = repo.index().unwrap();
-            index.write_tree().unwrap()
-        };
-        let tree = repo.find_tree(tree_id).unwrap();
-        repo.commit(Some("HEAD"), &sig, &sig, "Initial", &tree, &[]).unwrap();
-
-        let result = ship(tmp.path().to_str().unwrap());
-        match result {
-            Err(GitError::NoRemote) => {} // expected
-            other => panic!("Expected NoRemote, got: {:?}", other),
-        }
+    fn init_update_and_sync_a_fresh_clone() {
+        let temp = tempfile::tempdir().unwrap();
+        let (lib, app) = setup_superproject(&temp);
+        let clone = temp.path().join("clone");
+        run_git(temp.path(), ["clone", path_str(&app), path_str(&clone)]);
+
+        let fresh = list_submodules(path_str(&clone)).unwrap().remove(0);
+        assert!(!fresh.is_initialized);
+        assert!(fresh.checked_out_sha.is_none());
+
+        let initialized = init_submodules(path_str(&clone)).unwrap().remove(0);
+        assert!(initialized.is_initialized);
+
+        let updated = update_submodules(path_str(&clone), true).unwrap().remove(0);
+        assert_eq!(updated.checked_out_sha, updated.recorded_sha);
+        assert!(clone.join("vendor/lib/lib.txt").exists());
+
+        let moved = temp.path().join("lib-moved");
+        std::fs::rename(&lib, &moved).unwrap();
+        run_git(&clone, ["config", "-f", ".gitmodules", "submodule.vendor/lib.url", path_str(&moved)]);
+        let synced = sync_submodules(path_str(&clone)).unwrap().remove(0);
+        assert_eq!(synced.url.as_deref(), Some(path_str(&moved)));
+        assert_eq!(
+            run_git(&clone, ["config", "submodule.vendor/lib.url"]).trim(),
+            path_str(&moved)
+        );
     }
+}
 
-    #[test]
-    fn ship_simple_push_updates_remote_and_clears_ahead() {
-        let (_temp, repo_path, remote_path) = setup_repo_with_remote();
-        commit_file(&repo_path, "file.txt", "hello\n", "initial commit");
+#[cfg(test)]
+mod signing_tests {
+    use super::test_support::*;
+    use super::*;
 
-        let result = ship(repo_path.to_str().unwrap()).unwrap();
-        assert!(result.pushed);
-        assert_eq!(result.branch, "main");
-        assert_eq!(result.remote, "origin");
-        assert_eq!(result.commits_pushed, 1);
-        assert!(!result.rebased);
+    fn setup_ssh_signing() -> (tempfile::TempDir, std::path::PathBuf) {
+        let (temp, repo_path) = init_test_repo();
+        let key = temp.path().join("signing_key");
+        let output = std::process::Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", "test@example.com", "-f", path_str(&key)])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
 
-        let status = get_status(repo_path.to_str().unwrap()).unwrap();
-        assert_eq!(status.ahead, 0);
-        assert_eq!(status.behind, 0);
-        assert!(status.has_remote);
+        let public_key = std::fs::read_to_string(temp.path().join("signing_key.pub")).unwrap();
+        let allowed = temp.path().join("allowed_signers");
+        std::fs::write(&allowed, format!("test@example.com {}", public_key)).unwrap();
 
-        let remote_head_output = run_git(&remote_path, ["rev-parse", "refs/heads/main"]);
-        let remote_head = String::from_utf8(remote_head_output.stdout).unwrap().trim().to_string();
-        assert_eq!(get_head(&repo_path), remote_head);
+        run_git(&repo_path, ["config", "commit.gpgsign", "true"]);
+        run_git(&repo_path, ["config", "gpg.format", "ssh"]);
+        run_git(&repo_path, ["config", "user.signingkey", path_str(&key)]);
+        run_git(&repo_path, ["config", "gpg.ssh.allowedSignersFile", path_str(&allowed)]);
+        (temp, repo_path)
     }
 
     #[test]
-    fn ship_rebases_then_pushes_when_remote_is_ahead() {
-        let (_temp, repo_path, remote_path) = setup_repo_with_remote();
-        commit_file(&repo_path, "shared.txt", "base\n", "base commit");
-        run_git(&repo_path, ["push", "-u", "origin", "main"]);
-
-        let other_dir = tempfile::tempdir().unwrap();
-        let other_path = other_dir.path().join("other");
-        clone_repo_on_branch(&remote_path, &other_path, "main");
-        run_git(&other_path, ["config", "user.name", "Remote User"]);
-        run_git(&other_path, ["config", "user.email", "remote@example.com"]);
-        commit_file(&other_path, "shared.txt", "base\nremote change\n", "remote commit");
-        run_git(&other_path, ["push", "origin", "main"]);
-
-        run_git(&repo_path, ["fetch", "origin"]);
+    fn save_and_amend_create_ssh_signed_commits() {
+        let (_temp, repo_path) = setup_ssh_signing();
+        write_file(&repo_path, "file.txt", "one\n");
+        save(path_str(&repo_path), Some("First".to_string())).unwrap();
+        run_git(&repo_path, ["verify-commit", "HEAD"]);
+
+        write_file(&repo_path, "file.txt", "two\n");
+        save(path_str(&repo_path), Some("Second".to_string())).unwrap();
+        run_git(&repo_path, ["verify-commit", "HEAD"]);
+        assert_eq!(run_git(&repo_path, ["rev-list", "--count", "HEAD"]).trim(), "2");
+
+        write_file(&repo_path, "file.txt", "three\n");
+        let amended = amend_save(path_str(&repo_path), Some("Second, amended".to_string())).unwrap();
+        run_git(&repo_path, ["verify-commit", "HEAD"]);
+        assert_eq!(run_git(&repo_path, ["rev-parse", "HEAD"]).trim(), amended.sha);
+        assert_eq!(run_git(&repo_path, ["rev-list", "--count", "HEAD"]).trim(), "2");
+        assert!(run_git(&repo_path, ["reflog", "-1"]).contains("commit (amend): Second, amended"));
+    }
 
-        commit_file(&repo_path, "local.txt", "local change\n", "local commit");
+    #[test]
+    fn signing_failure_is_reported_and_leaves_head_alone() {
+        let (temp, repo_path) = setup_ssh_signing();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        let head = run_git(&repo_path, ["rev-parse", "HEAD"]);
+        let missing = temp.path().join("missing_key");
+        run_git(&repo_path, ["config", "user.signingkey", path_str(&missing)]);
+
+        write_file(&repo_path, "file.txt", "changed\n");
+        match save(path_str(&repo_path), None) {
+            Err(GitError::SigningFailed(_)) => {}
+            other => panic!("Expected signing failure, got: {:?}", other),
+        }
+        assert_eq!(run_git(&repo_path, ["rev-parse", "HEAD"]), head);
+    }
+}
 
-        let result = ship(repo_path.to_str().unwrap()).unwrap();
-        assert!(result.pushed);
-        assert!(result.rebased);
-        assert_eq!(result.commits_pushed, 1);
+#[cfg(all(test, unix))]
+mod hook_tests {
+    use super::test_support::*;
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn install_hook(dir: &Path, name: &str, script: &str) {
+        fs::create_dir_all(dir).unwrap();
+        let hook = dir.join(name);
+        fs::write(&hook, format!("#!/bin/sh\n{}", script)).unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+    }
 
-        let status = get_status(repo_path.to_str().unwrap()).unwrap();
-        assert_eq!(status.ahead, 0);
-        assert_eq!(status.behind, 0);
+    #[test]
+    fn failing_pre_commit_aborts_save_unless_hooks_are_skipped() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        let hooks = repo_path.join(".git/hooks");
+        install_hook(&hooks, "pre-commit", "echo 'lint failed: file.txt'\nexit 1\n");
+        install_hook(&hooks, "post-commit", "touch post-commit-ran\n");
+
+        write_file(&repo_path, "file.txt", "broken\n");
+        match save(path_str(&repo_path), Some("Broken".to_string())) {
+            Err(GitError::HookFailed { hook, output }) => {
+                assert_eq!(hook, "pre-commit");
+                assert_eq!(output, "lint failed: file.txt");
+            }
+            other => panic!("Expected hook failure, got: {:?}", other),
+        }
+        assert_eq!(run_git(&repo_path, ["log", "-1", "--format=%s"]).trim(), "base");
 
-        let log_output = run_git(&repo_path, ["log", "--oneline", "--decorate=short", "-3"]);
-        let log = String::from_utf8(log_output.stdout).unwrap();
-        assert!(log.contains("local commit"));
-        assert!(log.contains("remote commit"));
+        let options = SaveOptions { skip_hooks: true, ..Default::default() };
+        save_with_options(path_str(&repo_path), Some("Skipped".to_string()), &options).unwrap();
+        assert_eq!(run_git(&repo_path, ["log", "-1", "--format=%s"]).trim(), "Skipped");
+        assert!(!repo_path.join("post-commit-ran").exists());
+    }
 
-        let remote_head = String::from_utf8(run_git(&remote_path, ["rev-parse", "refs/heads/main"]).stdout)
-            .unwrap()
-            .trim()
-            .to_string();
-        assert_eq!(get_head(&repo_path), remote_head);
-        assert!(String::from_utf8(run_git(&remote_path, ["show", "refs/heads/main:local.txt"]).stdout)
-            .unwrap()
-            .contains("local change"));
-        assert!(String::from_utf8(run_git(&remote_path, ["show", "refs/heads/main:shared.txt"]).stdout)
-            .unwrap()
-            .contains("remote change"));
+    #[test]
+    fn hooks_from_hooks_path_restage_and_rewrite_the_message() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        run_git(&repo_path, ["config", "core.hooksPath", ".husky"]);
+        let hooks = repo_path.join(".husky");
+        // Formats staged files and restages them, like lint-staged
+        install_hook(
+            &hooks,
+            "pre-commit",
+            "tr a-z A-Z < file.txt > file.tmp && mv file.tmp file.txt && git add file.txt\n",
+        );
+        install_hook(&hooks, "prepare-commit-msg", "echo \"$2\" > prepare-source\n");
+        install_hook(&hooks, "commit-msg", "printf '%s\\n\\nRefs: #42\\n' \"$(cat \"$1\")\" > \"$1\"\n");
+        install_hook(&hooks, "post-commit", "git rev-parse HEAD > post-commit-head\n");
+
+        write_file(&repo_path, "file.txt", "changed\n");
+        let result = save(path_str(&repo_path), Some("Change file".to_string())).unwrap();
+
+        assert_eq!(result.message, "Change file\n\nRefs: #42");
+        assert_eq!(run_git(&repo_path, ["show", "HEAD:file.txt"]), "CHANGED\n");
+        assert_eq!(fs::read_to_string(repo_path.join("prepare-source")).unwrap(), "message\n");
+        assert_eq!(
+            fs::read_to_string(repo_path.join("post-commit-head")).unwrap().trim(),
+            result.sha
+        );
     }
 
     #[test]
-    fn ship_conflict_aborts_rebase_and_leaves_repo_clean() {
-        let (_temp, repo_path, remote_path) = setup_repo_with_remote();
-        commit_file(&repo_path, "shared.txt", "base\n", "base commit");
-        run_git(&repo_path, ["push", "-u", "origin", "main"]);
+    fn failing_commit_msg_hook_reports_its_output() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        install_hook(
+            &repo_path.join(".git/hooks"),
+            "commit-msg",
+            "grep -q '^feat\\|^fix' \"$1\" || { echo 'subject must start with feat or fix' >&2; exit 1; }\n",
+        );
 
-        let before_ship_head = get_head(&repo_path);
+        write_file(&repo_path, "file.txt", "changed\n");
+        match save(path_str(&repo_path), Some("Change file".to_string())) {
+            Err(GitError::HookFailed { hook, output }) => {
+                assert_eq!(hook, "commit-msg");
+                assert_eq!(output, "subject must start with feat or fix");
+            }
+            other => panic!("Expected hook failure, got: {:?}", other),
+        }
+        save(path_str(&repo_path), Some("fix: change file".to_string())).unwrap();
+    }
+}
 
-        let other_dir = tempfile::tempdir().unwrap();
-        let other_path = other_dir.path().join("other");
-        clone_repo_on_branch(&remote_path, &other_path, "main");
-        run_git(&other_path, ["config", "user.name", "Remote User"]);
-        run_git(&other_path, ["config", "user.email", "remote@example.com"]);
-        commit_file(&other_path, "shared.txt", "remote version\n", "remote conflict");
-        run_git(&other_path, ["push", "origin", "main"]);
+#[cfg(test)]
+mod save_mode_tests {
+    use super::test_support::*;
+    use super::*;
 
-        run_git(&repo_path, ["fetch", "origin"]);
+    fn save_mode(repo_path: &Path, mode: SaveMode) -> Result<SaveResult, GitError> {
+        let options = SaveOptions { mode, ..Default::default() };
+        save_with_options(path_str(repo_path), Some("Selective".to_string()), &options)
+    }
 
-        commit_file(&repo_path, "shared.txt", "local version\n", "local conflict");
-        let local_head_before_ship = get_head(&repo_path);
+    fn committed_files(repo_path: &Path) -> Vec<String> {
+        run_git(repo_path, ["show", "--name-status", "--format=", "HEAD"])
+            .lines()
+            .map(|line| line.replace('\t', " "))
+            .collect()
+    }
 
-        let result = ship(repo_path.to_str().unwrap());
-        match result {
-            Err(GitError::RebaseConflict(message)) => assert!(message.to_lowercase().contains("conflict")),
-            other => panic!("Expected RebaseConflict, got: {:?}", other),
-        }
+    #[test]
+    fn files_committed_counts_changed_files_only() {
+        let (_temp, repo_path) = init_test_repo();
+        write_file(&repo_path, "a.txt", "a\n");
+        write_file(&repo_path, "b.txt", "b\n");
+        let initial = save(path_str(&repo_path), None).unwrap();
+        assert_eq!(initial.files_committed, 2);
+        assert_eq!(initial.message, "Update 2 files");
+
+        write_file(&repo_path, "a.txt", "changed\n");
+        let result = save(path_str(&repo_path), None).unwrap();
+        assert_eq!(result.files_committed, 1);
+        assert_eq!(result.message, "Update 1 file");
+    }
 
-        assert_eq!(get_head(&repo_path), local_head_before_ship);
-        assert_ne!(local_head_before_ship, before_ship_head);
-        assert!(!has_rebase_state(&repo_path));
+    #[test]
+    fn staged_mode_commits_only_the_index() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "a.txt", "a\n", "a");
+        commit_file(&repo_path, "b.txt", "b\n", "b");
+
+        assert!(matches!(save_mode(&repo_path, SaveMode::Staged), Err(GitError::NothingToCommit)));
+
+        write_file(&repo_path, "a.txt", "staged\n");
+        run_git(&repo_path, ["add", "a.txt"]);
+        write_file(&repo_path, "a.txt", "staged then edited\n");
+        write_file(&repo_path, "b.txt", "unstaged\n");
+
+        let result = save_mode(&repo_path, SaveMode::Staged).unwrap();
+        assert_eq!(result.files_committed, 1);
+        assert_eq!(committed_files(&repo_path), vec!["M a.txt"]);
+        assert_eq!(run_git(&repo_path, ["show", "HEAD:a.txt"]), "staged\n");
+        assert_eq!(
+            run_git(&repo_path, ["status", "--porcelain"]),
+            " M a.txt\n M b.txt\n"
+        );
+    }
 
-        let status_output = run_git(&repo_path, ["status", "--short"]);
-        assert!(String::from_utf8(status_output.stdout).unwrap().trim().is_empty());
-        assert_eq!(fs::read_to_string(repo_path.join("shared.txt")).unwrap(), "local version\n");
+    #[test]
+    fn paths_mode_commits_listed_files_and_keeps_other_staged_changes() {
+        let (_temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "a.txt", "a\n", "a");
+        commit_file(&repo_path, "staged.txt", "s\n", "staged");
+        commit_file(&repo_path, "gone.txt", "g\n", "gone");
+
+        write_file(&repo_path, "staged.txt", "staged change\n");
+        run_git(&repo_path, ["add", "staged.txt"]);
+        write_file(&repo_path, "a.txt", "picked\n");
+        std::fs::remove_file(repo_path.join("gone.txt")).unwrap();
+        write_file(&repo_path, "new/file.txt", "new\n");
+        write_file(&repo_path, "left.txt", "untracked\n");
+
+        let paths = vec!["a.txt".to_string(), "gone.txt".to_string(), "new".to_string()];
+        let result = save_mode(&repo_path, SaveMode::Paths { paths }).unwrap();
+        assert_eq!(result.files_committed, 3);
+        assert_eq!(committed_files(&repo_path), vec!["M a.txt", "D gone.txt", "A new/file.txt"]);
+        assert_eq!(
+            run_git(&repo_path, ["status", "--porcelain"]),
+            "M  staged.txt\n?? left.txt\n"
+        );
 
-        let rebase_abort_check = run_git_allow_failure(&repo_path, ["rebase", "--abort"]);
-        assert!(!rebase_abort_check.status.success());
+        let none = save_mode(&repo_path, SaveMode::Paths { paths: vec!["a.txt".to_string()] });
+        assert!(matches!(none, Err(GitError::NothingToCommit)));
+        assert_eq!(
+            run_git(&repo_path, ["status", "--porcelain"]),
+            "M  staged.txt\n?? left.txt\n"
+        );
     }
 }
@@ -0,0 +1,324 @@
+use crate::git::{self, DiscardBackup, GitError, RefSnapshot};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+const MAX_JOURNAL_ENTRIES: usize = 200;
+
+/// Operations that only move refs; undoing them keeps the index and working
+/// tree, like a soft reset.
+const SOFT_UNDO_KINDS: [&str; 3] = ["save", "amend_save", "undo_save"];
+
+/// Operations whose effects live on a remote and cannot be undone locally.
+const IRREVERSIBLE_KINDS: [&str; 3] = ["ship", "ship_release", "sync_push"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationRecord {
+    pub id: String,
+    pub kind: String,
+    pub timestamp: i64,
+    pub repo_path: String,
+    pub before: RefSnapshot,
+    pub after: RefSnapshot,
+    pub backups: Vec<DiscardBackup>,
+    pub undone: bool,
+}
+
+fn journal_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("vibogit").join("journal"))
+}
+
+fn journal_file(dir: &Path, repo_path: &str) -> PathBuf {
+    let key = std::fs::canonicalize(repo_path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| repo_path.to_string());
+    let digest = Sha256::digest(key.as_bytes());
+    let name: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    dir.join(format!("{}.json", name))
+}
+
+fn load_records(dir: &Path, repo_path: &str) -> Result<Vec<OperationRecord>, GitError> {
+    let content = match std::fs::read_to_string(journal_file(dir, repo_path)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+    serde_json::from_str(&content)
+        .map_err(|e| GitError::Io(format!("Operation journal is corrupt: {}", e)))
+}
+
+fn persist_records(dir: &Path, repo_path: &str, records: &[OperationRecord]) -> Result<(), GitError> {
+    std::fs::create_dir_all(dir)?;
+    let content = serde_json::to_string_pretty(records)
+        .map_err(|e| GitError::Io(format!("Failed to serialize operation journal: {}", e)))?;
+    std::fs::write(journal_file(dir, repo_path), content)?;
+    Ok(())
+}
+
+/// Snapshot refs ahead of a mutating git call. Failures are swallowed so the
+/// journal never blocks the operation itself.
+pub fn begin(repo_path: &str) -> Option<RefSnapshot> {
+    git::capture_ref_snapshot(repo_path).ok()
+}
+
+/// Record a completed operation in the journal under the vibogit config dir.
+pub fn record(repo_path: &str, kind: &str, before: Option<RefSnapshot>, backups: Vec<DiscardBackup>) {
+    let Some(dir) = journal_dir() else {
+        return;
+    };
+    if let Err(e) = record_in(&dir, repo_path, kind, before, backups) {
+        eprintln!("Failed to record {} in operation journal: {}", kind, e);
+    }
+}
+
+fn record_in(
+    dir: &Path,
+    repo_path: &str,
+    kind: &str,
+    before: Option<RefSnapshot>,
+    backups: Vec<DiscardBackup>,
+) -> Result<Option<OperationRecord>, GitError> {
+    let Some(before) = before else {
+        return Ok(None);
+    };
+    let after = git::capture_ref_snapshot(repo_path)?;
+    if before == after && backups.is_empty() {
+        return Ok(None);
+    }
+
+    let now = chrono::Utc::now();
+    let entry = OperationRecord {
+        id: format!("{}-{}", now.timestamp_millis(), kind),
+        kind: kind.to_string(),
+        timestamp: now.timestamp(),
+        repo_path: repo_path.to_string(),
+        before,
+        after,
+        backups,
+        undone: false,
+    };
+
+    let mut records = load_records(dir, repo_path)?;
+    records.push(entry.clone());
+    if records.len() > MAX_JOURNAL_ENTRIES {
+        let excess = records.len() - MAX_JOURNAL_ENTRIES;
+        records.drain(..excess);
+    }
+    persist_records(dir, repo_path, &records)?;
+
+    Ok(Some(entry))
+}
+
+/// Journal entries for a project, newest first.
+pub fn list_operations(repo_path: &str) -> Result<Vec<OperationRecord>, GitError> {
+    let dir = journal_dir().ok_or_else(|| GitError::Io("No config directory available".to_string()))?;
+    list_operations_in(&dir, repo_path)
+}
+
+fn list_operations_in(dir: &Path, repo_path: &str) -> Result<Vec<OperationRecord>, GitError> {
+    let mut records = load_records(dir, repo_path)?;
+    records.reverse();
+    Ok(records)
+}
+
+/// Restore the state from before the most recent operation that has not been
+/// undone yet.
+pub fn undo_last_operation(repo_path: &str) -> Result<OperationRecord, GitError> {
+    let dir = journal_dir().ok_or_else(|| GitError::Io("No config directory available".to_string()))?;
+    undo_last_operation_in(&dir, repo_path)
+}
+
+fn undo_last_operation_in(dir: &Path, repo_path: &str) -> Result<OperationRecord, GitError> {
+    let mut records = load_records(dir, repo_path)?;
+    let Some(target) = records.iter().rposition(|entry| !entry.undone) else {
+        return Err(GitError::Git2("Nothing to undo".to_string()));
+    };
+
+    let entry = &records[target];
+    if IRREVERSIBLE_KINDS.contains(&entry.kind.as_str()) {
+        return Err(GitError::Git2(format!(
+            "The last operation ({}) changed the remote and cannot be undone",
+            entry.kind
+        )));
+    }
+
+    if entry.before != entry.after {
+        git::ensure_snapshot_not_pushed(repo_path, &entry.before, &entry.after)?;
+        let keep_worktree = SOFT_UNDO_KINDS.contains(&entry.kind.as_str());
+        git::restore_ref_snapshot(repo_path, &entry.before, &entry.after, keep_worktree)?;
+    }
+    if !entry.backups.is_empty() {
+        git::restore_discard_backups(repo_path, &entry.backups)?;
+    }
+
+    records[target].undone = true;
+    let undone = records[target].clone();
+    persist_records(dir, repo_path, &records)?;
+
+    Ok(undone)
+}
+
+#[cfg(test)]
+mod journal_tests {
+    use super::*;
+    use crate::git::test_support::*;
+    use crate::git::DiscardTarget;
+    use std::{fs, path::Path};
+
+    fn setup() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let (temp, repo_path) = init_test_repo();
+        commit_file(&repo_path, "file.txt", "base\n", "base");
+        let journal = temp.path().join("journal");
+        (temp, repo_path, journal)
+    }
+
+    fn head(repo_path: &Path) -> String {
+        run_git(repo_path, ["rev-parse", "HEAD"]).trim().to_string()
+    }
+
+    #[test]
+    fn undo_save_moves_head_back_and_keeps_changes() {
+        let (_temp, repo_path, journal) = setup();
+        let path = repo_path.to_str().unwrap();
+        let base = head(&repo_path);
+
+        fs::write(repo_path.join("file.txt"), "changed\n").unwrap();
+        let before = begin(path);
+        git::save(path, Some("quick save".to_string())).unwrap();
+        record_in(&journal, path, "save", before, vec![]).unwrap().unwrap();
+
+        let undone = undo_last_operation_in(&journal, path).unwrap();
+        assert_eq!(undone.kind, "save");
+        assert_eq!(head(&repo_path), base);
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "changed\n");
+        assert!(list_operations_in(&journal, path).unwrap()[0].undone);
+
+        match undo_last_operation_in(&journal, path) {
+            Err(GitError::Git2(message)) => assert_eq!(message, "Nothing to undo"),
+            other => panic!("Expected nothing to undo, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn undo_branch_creation_with_checkout_returns_to_previous_branch() {
+        let (_temp, repo_path, journal) = setup();
+        let path = repo_path.to_str().unwrap();
+
+        let before = begin(path);
+        git::create_branch(path, "feature", true).unwrap();
+        record_in(&journal, path, "create_branch", before, vec![]).unwrap();
+
+        undo_last_operation_in(&journal, path).unwrap();
+        assert_eq!(run_git(&repo_path, ["branch", "--show-current"]).trim(), "main");
+        assert!(run_git(&repo_path, ["branch", "--list", "feature"]).trim().is_empty());
+    }
+
+    #[test]
+    fn undo_discard_restores_backed_up_contents() {
+        let (_temp, repo_path, journal) = setup();
+        let path = repo_path.to_str().unwrap();
+
+        fs::write(repo_path.join("file.txt"), "precious\n").unwrap();
+        let before = begin(path);
+        let result = git::discard_files(path, &["file.txt".to_string()], DiscardTarget::Index).unwrap();
+        record_in(&journal, path, "discard", before, result.backups).unwrap();
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "base\n");
+
+        undo_last_operation_in(&journal, path).unwrap();
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "precious\n");
+    }
+
+    #[test]
+    fn undo_stash_save_pops_the_stash() {
+        let (_temp, repo_path, journal) = setup();
+        let path = repo_path.to_str().unwrap();
+
+        fs::write(repo_path.join("file.txt"), "wip\n").unwrap();
+        let before = begin(path);
+        git::stash_save(path, None).unwrap();
+        record_in(&journal, path, "stash_save", before, vec![]).unwrap();
+
+        undo_last_operation_in(&journal, path).unwrap();
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "wip\n");
+        assert!(run_git(&repo_path, ["stash", "list"]).trim().is_empty());
+    }
+
+    #[test]
+    fn undo_refuses_when_repository_changed_outside_journal() {
+        let (_temp, repo_path, journal) = setup();
+        let path = repo_path.to_str().unwrap();
+
+        fs::write(repo_path.join("file.txt"), "changed\n").unwrap();
+        let before = begin(path);
+        git::save(path, None).unwrap();
+        record_in(&journal, path, "save", before, vec![]).unwrap();
+
+        fs::write(repo_path.join("file.txt"), "external\n").unwrap();
+        run_git(&repo_path, ["commit", "-am", "external commit"]);
+
+        assert!(undo_last_operation_in(&journal, path).is_err());
+        assert!(!list_operations_in(&journal, path).unwrap()[0].undone);
+    }
+
+    #[test]
+    fn undo_refuses_when_only_remote_operations_remain() {
+        let (_temp, repo_path, journal) = setup();
+        let path = repo_path.to_str().unwrap();
+
+        fs::write(repo_path.join("file.txt"), "shipped\n").unwrap();
+        let before = begin(path);
+        git::save(path, None).unwrap();
+        record_in(&journal, path, "ship", before, vec![]).unwrap();
+
+        assert!(undo_last_operation_in(&journal, path).is_err());
+        assert!(!list_operations_in(&journal, path).unwrap()[0].undone);
+    }
+
+    #[test]
+    fn undo_refuses_when_the_change_has_been_pushed() {
+        let (temp, repo_path, journal) = setup();
+        let path = repo_path.to_str().unwrap();
+        let remote_path = temp.path().join("remote.git");
+        run_git(temp.path(), ["init", "--bare", path_str(&remote_path)]);
+        run_git(&repo_path, ["remote", "add", "origin", path_str(&remote_path)]);
+        run_git(&repo_path, ["push", "-u", "origin", "main"]);
+
+        fs::write(repo_path.join("file.txt"), "shipped\n").unwrap();
+        let before = begin(path);
+        git::save(path, None).unwrap();
+        record_in(&journal, path, "save", before, vec![]).unwrap();
+        run_git(&repo_path, ["push"]);
+
+        match undo_last_operation_in(&journal, path) {
+            Err(GitError::AlreadyPushed(_)) => {}
+            other => panic!("Expected AlreadyPushed, got: {:?}", other),
+        }
+        assert!(!list_operations_in(&journal, path).unwrap()[0].undone);
+    }
+
+    #[test]
+    fn corrupt_journal_is_reported_instead_of_overwritten() {
+        let (_temp, repo_path, journal) = setup();
+        let path = repo_path.to_str().unwrap();
+        fs::create_dir_all(&journal).unwrap();
+        fs::write(journal_file(&journal, path), "not json").unwrap();
+
+        fs::write(repo_path.join("file.txt"), "changed\n").unwrap();
+        let before = begin(path);
+        git::save(path, None).unwrap();
+        assert!(record_in(&journal, path, "save", before, vec![]).is_err());
+        assert!(list_operations_in(&journal, path).is_err());
+        assert_eq!(fs::read_to_string(journal_file(&journal, path)).unwrap(), "not json");
+    }
+
+    #[test]
+    fn unchanged_operations_are_not_recorded() {
+        let (_temp, repo_path, journal) = setup();
+        let path = repo_path.to_str().unwrap();
+
+        let before = begin(path);
+        assert!(record_in(&journal, path, "checkout", before, vec![]).unwrap().is_none());
+        assert!(list_operations_in(&journal, path).unwrap().is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 mod commands;
 mod git;
+mod journal;
 mod tray;
 mod watcher;
 
@@ -60,20 +61,77 @@ pub fn run() {
             // Git commands
             commands::git_status,
             commands::git_save,
+            commands::git_undo_save,
+            commands::git_amend_save,
+            commands::git_plan_rebase,
+            commands::git_execute_rebase,
             commands::git_ship,
             commands::git_sync,
+            commands::git_get_sync_strategy,
+            commands::git_set_sync_strategy,
             commands::git_fetch,
             commands::git_log,
+            commands::git_query_history,
+            commands::git_show_commit,
+            commands::git_compare,
             commands::git_diff,
             commands::git_stage,
             commands::git_unstage,
+            commands::git_stage_hunks,
+            commands::git_unstage_hunks,
+            commands::git_discard_files,
+            commands::git_discard_hunks,
+            commands::git_restore_discard_backups,
             commands::git_checkout,
             commands::git_create_branch,
+            commands::git_delete_branch,
+            commands::git_rename_branch,
+            commands::git_set_upstream,
+            commands::git_merge_branch,
+            commands::git_cherry_pick,
+            commands::git_revert,
+            commands::git_delete_merged_branches,
+            commands::git_worktrees,
+            commands::git_add_worktree,
+            commands::git_lock_worktree,
+            commands::git_unlock_worktree,
+            commands::git_prune_worktrees,
+            commands::git_remove_worktree,
+            commands::git_submodules,
+            commands::git_init_submodules,
+            commands::git_update_submodules,
+            commands::git_sync_submodules,
             commands::git_branches,
             commands::git_remotes,
+            commands::git_add_remote,
+            commands::git_remove_remote,
+            commands::git_rename_remote,
+            commands::git_set_remote_url,
             commands::git_stash_save,
             commands::git_stash_pop,
+            commands::git_stash_list,
+            commands::git_stash_diff,
+            commands::git_stash_apply,
+            commands::git_stash_drop,
+            commands::git_stash_branch,
+            commands::git_tags,
+            commands::git_create_tag,
+            commands::git_delete_tag,
+            commands::git_push_tags,
+            commands::git_release,
             commands::git_file_diff,
+            commands::git_blame,
+            commands::git_file_history,
+            commands::git_conflicts,
+            commands::git_conflict_detail,
+            commands::git_resolve_conflict,
+            commands::git_continue_operation,
+            commands::git_skip_operation,
+            commands::git_abort_operation,
+            commands::git_health_check,
+            commands::git_recover,
+            commands::git_list_operations,
+            commands::git_undo_last_operation,
             commands::git_init,
             // Project commands
             commands::set_project,
@@ -107,6 +107,8 @@ export interface GitDiff {
   hunks: DiffHunk[];
   oldPath?: string;
   isBinary: boolean;
+  isNew?: boolean;
+  isDeleted?: boolean;
 }
 
 export interface DiffHunk {
@@ -6,7 +6,7 @@ import { getSettings } from "@/lib/settings";
 import { getModelForProvider } from "@/lib/ai-service";
 import { Button } from "@/components/ui/button";
 import { X, Sparkles, Loader2, ExternalLink, GitPullRequest } from "lucide-react";
-import type { GitCommit, GitBranch } from "@vibogit/shared";
+import type { GitBranch } from "@vibogit/shared";
 
 interface CreatePRDialogProps {
   isOpen: boolean;
@@ -76,27 +76,17 @@ export function CreatePRDialog({
     setError(null);
 
     try {
-      // Get recent commits on this branch
-      const logResponse = await send<{ commits: GitCommit[] }>("log", {
-        repoPath,
-        limit: 10,
-      });
-
-      const commits = logResponse.commits.map(
-        (c) => `- ${c.hashShort}: ${c.message}`
-      );
-
-      const diffSummary = `Changes in ${currentBranch.name} branch`;
-
+      // The backend reads commits and the diff from the branches via `path`
       const { invoke } = await import("@tauri-apps/api/core");
       const data = await invoke<{ title: string; description: string }>("ai_generate_pr", {
-        commits,
-        diff: diffSummary,
+        commits: [],
+        diff: "",
         baseBranch,
         headBranch: currentBranch.name,
         provider: settings.aiProvider,
         model: getModelForProvider(settings.aiProvider, settings.aiModel),
         apiKey: settings.aiApiKey,
+        path: repoPath,
       });
       setTitle(data.title);
       setDescription(data.description);
@@ -1,6 +1,6 @@
 "use client";
 
-import { useMemo, useRef, useState } from "react";
+import { useMemo, useRef, useState, type ReactNode } from "react";
 import {
   DndContext,
   DragOverlay,
@@ -33,6 +33,21 @@ interface ProjectListProps {
 
 type DropPosition = "before" | "after";
 
+// Worktrees render under their repository; everything else keeps the stored order.
+function groupWorktrees(projects: Project[]) {
+  const paths = new Set(projects.map(project => project.path));
+  const topLevel: Project[] = [];
+  const worktreesByMain = new Map<string, Project[]>();
+  for (const project of projects) {
+    if (project.worktreeOf && paths.has(project.worktreeOf)) {
+      worktreesByMain.set(project.worktreeOf, [...(worktreesByMain.get(project.worktreeOf) ?? []), project]);
+    } else {
+      topLevel.push(project);
+    }
+  }
+  return { topLevel, worktreesByMain };
+}
+
 interface SortableProjectRowProps {
   project: Project;
   status?: ProjectStatus;
@@ -44,6 +59,7 @@ interface SortableProjectRowProps {
   isDragActive: boolean;
   onClick: () => void;
   onRemove: () => void;
+  children?: ReactNode;
 }
 
 function SortableProjectRow({
@@ -57,6 +73,7 @@ function SortableProjectRow({
   isDragActive,
   onClick,
   onRemove,
+  children,
 }: SortableProjectRowProps) {
   const {
     attributes,
@@ -104,6 +121,7 @@ function SortableProjectRow({
         onClick={onClick}
         onRemove={onRemove}
       />
+      {children}
     </div>
   );
 }
@@ -115,7 +133,8 @@ export function ProjectList({ isCollapsed, onProjectSelect }: ProjectListProps)
   const [overId, setOverId] = useState<string | null>(null);
   const suppressClickUntilRef = useRef(0);
   const isDragEnabled = !isCollapsed;
-  const projectPaths = useMemo(() => projects.map(project => project.path), [projects]);
+  const { topLevel, worktreesByMain } = useMemo(() => groupWorktrees(projects), [projects]);
+  const projectPaths = useMemo(() => topLevel.map(project => project.path), [topLevel]);
   const sensors = useSensors(
     useSensor(PointerSensor, { activationConstraint: { distance: 6 } }),
     useSensor(KeyboardSensor, { coordinateGetter: sortableKeyboardCoordinates })
@@ -163,7 +182,11 @@ export function ProjectList({ isCollapsed, onProjectSelect }: ProjectListProps)
       return;
     }
 
-    const reorderedPaths = arrayMove(projectPaths, fromIndex, toIndex);
+    // Worktrees move with their repository
+    const reorderedPaths = arrayMove(projectPaths, fromIndex, toIndex).flatMap(path => [
+      path,
+      ...(worktreesByMain.get(path) ?? []).map(worktree => worktree.path),
+    ]);
     void reorderProjects(reorderedPaths);
     clearDragState();
   };
@@ -205,7 +228,8 @@ export function ProjectList({ isCollapsed, onProjectSelect }: ProjectListProps)
     >
       <SortableContext items={projectPaths} strategy={verticalListSortingStrategy}>
         <div className="space-y-1 p-1">
-          {projects.map((project) => {
+          {topLevel.map((project) => {
+            const worktrees = worktreesByMain.get(project.path) ?? [];
             let dropPosition: DropPosition | null = null;
             if (
               isDragEnabled &&
@@ -232,7 +256,24 @@ export function ProjectList({ isCollapsed, onProjectSelect }: ProjectListProps)
                 isDragActive={activeId !== null}
                 onClick={() => handleSelect(project.path)}
                 onRemove={() => handleRemove(project.path)}
-              />
+              >
+                {worktrees.length > 0 && (
+                  <div className={cn("mt-1 space-y-1", !isCollapsed && "ml-4 border-l border-border pl-1")}>
+                    {worktrees.map((worktree) => (
+                      <ProjectItem
+                        key={worktree.path}
+                        project={worktree}
+                        status={statuses[worktree.path]}
+                        favicon={favicons[worktree.path]}
+                        isSelected={worktree.path === selectedPath}
+                        isCollapsed={isCollapsed}
+                        onClick={() => handleSelect(worktree.path)}
+                        onRemove={() => handleRemove(worktree.path)}
+                      />
+                    ))}
+                  </div>
+                )}
+              </SortableProjectRow>
             );
           })}
         </div>
@@ -31,6 +31,7 @@ export interface Project {
   path: string;
   name: string;
   addedAt: number;
+  worktreeOf?: string;
 }
 
 export interface ProjectStatus {
@@ -84,11 +85,19 @@ function projectsReducer(state: ProjectsState, action: ProjectsAction): Projects
   switch (action.type) {
     case "SET_PROJECTS":
       return { ...state, projects: action.payload, loading: false };
-    case "ADD_PROJECT":
-      return { 
-        ...state, 
-        projects: [...state.projects.filter(p => p.path !== action.payload.path), action.payload] 
+    case "ADD_PROJECT": {
+      const projects = state.projects.filter(p => p.path !== action.payload.path);
+      // A new worktree joins the end of its repository's group
+      const main = action.payload.worktreeOf;
+      const groupEnd = main ? projects.findLastIndex(p => p.path === main || p.worktreeOf === main) : -1;
+      if (groupEnd === -1) {
+        return { ...state, projects: [...projects, action.payload] };
+      }
+      return {
+        ...state,
+        projects: [...projects.slice(0, groupEnd + 1), action.payload, ...projects.slice(groupEnd + 1)],
       };
+    }
     case "REMOVE_PROJECT":
       return { 
         ...state, 